@@ -0,0 +1,139 @@
+//! A CSV of every resume that was generated, which can be used to keep track of job applications
+use std::path::Path;
+
+use anyhow::Context;
+
+
+/// The name of the ledger, which is kept in the output directory
+pub const APPLICATIONS_FILE_NAME: &str = "applications.csv";
+const HEADER: [&str; 7] = ["date", "company", "job_title", "url", "match_score", "output_path", "status"];
+/// The status given to new applications
+const DEFAULT_STATUS: &str = "generated";
+
+
+/// A row of the ledger
+pub struct Application {
+    /// When the resume was generated, as YYYY-MM-DD
+    pub date: String,
+    pub company: String,
+    pub job_title: String,
+    pub url: String,
+    /// From 0 to 1
+    pub match_score: f32,
+    pub output_path: String,
+    /// Meant to be updated by hand, e.g. to applied, interviewing, or rejected
+    pub status: String
+}
+
+
+impl Application {
+    /// Creates a row with the default status
+    pub fn new(date: String, company: String, job_title: String, url: String, match_score: f32, output_path: String) -> Self {
+        Self { date, company, job_title, url, match_score, output_path, status: DEFAULT_STATUS.into() }
+    }
+
+    fn to_row(&self) -> [String; 7] {
+        [
+            self.date.clone(),
+            self.company.clone(),
+            self.job_title.clone(),
+            self.url.clone(),
+            format!("{:.3}", self.match_score),
+            self.output_path.clone(),
+            self.status.clone()
+        ]
+    }
+}
+
+
+/// Splits CSV text into rows of fields, following RFC 4180
+pub(crate) fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c)
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+
+pub(crate) fn write_csv_row(out: &mut String, row: &[String]) {
+    let fields: Vec<_> = row
+        .iter()
+        .map(|field| if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.clone()
+        })
+        .collect();
+    *out += &fields.join(",");
+    *out += "\n";
+}
+
+
+/// Adds the given applications to the ledger at the given path, creating it if it does not exist
+///
+/// Applications whose URL is already in the ledger replace that row instead, keeping its status, so
+/// generating a resume again does not lose track of where an application is at.
+pub fn record(path: &Path, applications: Vec<Application>) -> anyhow::Result<()> {
+    if applications.is_empty() {
+        return Ok(())
+    }
+    let mut rows = match std::fs::read_to_string(path) {
+        Ok(text) => {
+            let mut rows = parse_csv(&text);
+            if !rows.is_empty() {
+                rows.remove(0);
+            }
+            rows
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(anyhow::Error::new(e).context(format!("Failed to read {path:?}. Do we have permissions?")))
+    };
+    let url_index = HEADER.iter().position(|x| *x == "url").unwrap();
+    let status_index = HEADER.iter().position(|x| *x == "status").unwrap();
+
+    for mut application in applications {
+        match rows.iter_mut().find(|row| row.get(url_index) == Some(&application.url)) {
+            Some(row) => {
+                if let Some(status) = row.get(status_index) {
+                    application.status = status.clone();
+                }
+                *row = application.to_row().into();
+            }
+            None => rows.push(application.to_row().into())
+        }
+    }
+
+    let mut out = String::new();
+    write_csv_row(&mut out, &HEADER.map(ToString::to_string));
+    for row in rows {
+        write_csv_row(&mut out, &row);
+    }
+    std::fs::write(path, out).context(format!("Failed to write {path:?}. Is it open in another program? Do we have permissions?"))
+}
@@ -0,0 +1,436 @@
+use std::{path::{Path, PathBuf}, hash::{Hash, Hasher}, io::{Read, Write}, sync::Mutex, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use anyhow::Context;
+use fxhash::{FxHasher, FxHashMap};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::page_scrapers::PageDataSerde;
+
+/// Put at the start of every cache file so that files from other versions can be recognized
+const CACHE_MAGIC: &[u8; 4] = b"RBCE";
+/// Must be incremented whenever the layout of `CacheEntry` (or anything inside of it) changes
+const CACHE_VERSION: u16 = 7;
+
+
+/// A scraped website, as stored in the cache
+#[derive(bitcode::Encode, bitcode::Decode)]
+pub struct CacheEntry {
+    /// The website that was scraped
+    pub url: String,
+    /// When the website was scraped, in seconds since the unix epoch
+    pub fetched_at: u64,
+    /// What was scraped from the website, or None if no scraper could collect anything
+    pub page_data: Option<PageDataSerde>,
+    /// The names of the scrapers that were enabled when the website was scraped, sorted
+    pub scrapers: Vec<String>,
+    /// The HTML of the website, compressed with zstd, so that it can be scraped again without fetching it
+    pub html: Option<Vec<u8>>
+}
+
+
+impl CacheEntry {
+    /// Creates an entry for a website that was just scraped
+    pub fn new<'a>(url: &Url, page_data: Option<PageDataSerde>, html: &str, scrapers: impl IntoIterator<Item=&'a String>) -> Self {
+        Self {
+            url: url.to_string(),
+            fetched_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            page_data,
+            scrapers: sorted_scrapers(scrapers),
+            html: zstd::encode_all(html.as_bytes(), 0).ok()
+        }
+    }
+
+    /// Decompresses the HTML of the website, if it was saved
+    pub fn html(&self) -> Option<anyhow::Result<String>> {
+        let compressed = self.html.as_ref()?;
+        Some(
+            zstd::decode_all(compressed.as_slice())
+                .map_err(anyhow::Error::from)
+                .and_then(|x| String::from_utf8(x).map_err(Into::into))
+                .context(format!("Failed to decompress the HTML of {}", self.url))
+        )
+    }
+
+    /// Serializes this entry, including a version header
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = CACHE_MAGIC.to_vec();
+        bytes.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        bytes.extend(bitcode::encode(self).unwrap());
+        bytes
+    }
+
+    /// Deserializes an entry written by `encode`
+    ///
+    /// Returns Ok(None) if the entry was written by a different version of the cache format,
+    /// in which case the website should just be scraped again.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Option<Self>> {
+        let Some((header, body)) = bytes.split_first_chunk::<6>() else { return Ok(None) };
+        if &header[..4] != CACHE_MAGIC || header[4..] != CACHE_VERSION.to_le_bytes() {
+            return Ok(None)
+        }
+        bitcode::decode(body).map(Some).map_err(Into::into)
+    }
+
+    /// How long ago the website was scraped
+    pub fn age(&self) -> Duration {
+        let fetched_at = UNIX_EPOCH + Duration::from_secs(self.fetched_at);
+        SystemTime::now().duration_since(fetched_at).unwrap_or_default()
+    }
+
+    /// Whether this entry should still be used instead of scraping the website again
+    ///
+    /// Entries with no page data are only kept for `no_data_retry`, and only if the same scrapers
+    /// are enabled, since the website may have just failed to load, or a newly enabled scraper may work on it.
+    pub fn is_fresh<'a>(&self, ttl: Option<Duration>, no_data_retry: Duration, scrapers: impl IntoIterator<Item=&'a String>) -> bool {
+        let age = self.age();
+        if ttl.is_some_and(|ttl| age >= ttl) {
+            return false
+        }
+        self.page_data.is_some() || (age < no_data_retry && self.scrapers == sorted_scrapers(scrapers))
+    }
+}
+
+
+fn sorted_scrapers<'a>(scrapers: impl IntoIterator<Item=&'a String>) -> Vec<String> {
+    let mut scrapers: Vec<String> = scrapers.into_iter().cloned().collect();
+    scrapers.sort();
+    scrapers
+}
+
+
+/// Query parameters that only track where a visitor came from, and do not change the page on any website
+const TRACKING_PARAMS: [&str; 7] = ["gclid", "fbclid", "msclkid", "gh_src", "gh_jid_src", "lever-source", "trk"];
+/// Query parameters that only track where a visitor came from on the given host (or its subdomains),
+/// as other websites may use the same names for what is on the page
+const HOST_TRACKING_PARAMS: [(&str, &[&str]); 1] = [("linkedin.com", &["refid", "trackingid"])];
+
+
+/// Normalizes a URL so that different links to the same page are cached and processed only once
+///
+/// Fragments and tracking query parameters (such as `utm_source`) are removed, as are trailing slashes.
+pub fn canonicalize_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+
+    let host = url.host_str().unwrap_or_default();
+    let host_tracking_params: Vec<&str> = HOST_TRACKING_PARAMS
+        .iter()
+        .filter(|(x, _)| host == *x || host.strip_suffix(x).is_some_and(|x| x.ends_with('.')))
+        .flat_map(|(_, params)| params.iter().copied())
+        .collect();
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| {
+            let key = key.to_lowercase();
+            !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str()) && !host_tracking_params.contains(&key.as_str())
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+
+    let path = url.path();
+    if path.len() > 1 && path.ends_with('/') {
+        let path = path.trim_end_matches('/').to_string();
+        url.set_path(&path);
+    }
+    url
+}
+
+
+/// How scraped websites are stored
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackend {
+    /// One file per website, named after the hash of its URL
+    #[default]
+    Files,
+    /// A single SQLite database with one row per website
+    Sqlite
+}
+
+
+/// Where a cache entry is stored, so that it can be deleted even if it cannot be read
+#[derive(Debug, Clone)]
+pub enum CacheKey {
+    File(PathBuf),
+    Row(String)
+}
+
+
+enum Storage {
+    Files,
+    Sqlite(Mutex<Connection>)
+}
+
+
+/// A store of scraped websites
+pub struct Cache {
+    dir: PathBuf,
+    storage: Storage
+}
+
+
+const SQLITE_FILE_NAME: &str = "cache.sqlite3";
+
+
+impl Cache {
+    /// Opens the cache in the given directory, creating the directory if needed
+    pub fn new(dir: impl Into<PathBuf>, backend: CacheBackend) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::DirBuilder::new().recursive(true).create(&dir).context(format!("Failed to create cache directory {dir:?}. Do we have permissions?"))?;
+        let storage = match backend {
+            CacheBackend::Files => Storage::Files,
+            CacheBackend::Sqlite => {
+                let path = dir.join(SQLITE_FILE_NAME);
+                let connection = Connection::open(&path).context(format!("Failed to open {path:?}. Do we have permissions?"))?;
+                // Other instances of resume-builder may be using the database at the same time
+                connection.busy_timeout(Duration::from_secs(30))?;
+                connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+                connection.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS entries (
+                        url TEXT PRIMARY KEY NOT NULL,
+                        fetched_at INTEGER NOT NULL,
+                        entry BLOB NOT NULL
+                    )"
+                )?;
+                Storage::Sqlite(Mutex::new(connection))
+            }
+        };
+        Ok(Self { dir, storage })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The path of the file that the given website is cached in, when using `CacheBackend::Files`
+    pub fn path(&self, url: &Url) -> PathBuf {
+        let mut hasher = FxHasher::default();
+        url.hash(&mut hasher);
+        self.dir.join(hasher.finish().to_string())
+    }
+
+    fn key(&self, url: &Url) -> CacheKey {
+        match self.storage {
+            Storage::Files => CacheKey::File(self.path(url)),
+            Storage::Sqlite(_) => CacheKey::Row(url.to_string())
+        }
+    }
+
+    /// Reads the raw bytes of an entry, if it exists
+    async fn read(&self, key: &CacheKey) -> anyhow::Result<Option<Vec<u8>>> {
+        match (&self.storage, key) {
+            (Storage::Files, CacheKey::File(path)) => {
+                if !tokio::fs::try_exists(path).await.context(format!("Failed to check if a website has been cached. Do we have read permissions for {:?}?", self.dir))? {
+                    return Ok(None)
+                }
+                tokio::fs::read(path).await.map(Some).context(format!("Failed to read {path:?}"))
+            }
+            (Storage::Sqlite(connection), CacheKey::Row(url)) => {
+                connection
+                    .lock()
+                    .unwrap()
+                    .query_row("SELECT entry FROM entries WHERE url = ?1", [url], |row| row.get(0))
+                    .optional()
+                    .context(format!("Failed to read the cache entry for {url}"))
+            }
+            _ => unreachable!("Cache keys should match the storage they came from")
+        }
+    }
+
+    /// Reads the cached entry for the given website, if it exists
+    ///
+    /// Entries from other versions of the cache format are treated as if they did not exist,
+    /// and will be replaced once the website is scraped again.
+    pub async fn get(&self, url: &Url) -> anyhow::Result<Option<CacheEntry>> {
+        let key = self.key(url);
+        let Some(bytes) = self.read(&key).await? else { return Ok(None) };
+        CacheEntry::decode(&bytes).context(format!("Failed to deserialize the cache entry {key:?}. Consider deleting it."))
+    }
+
+    /// Reads every entry in the cache, along with where it is stored
+    ///
+    /// Entries that could not be read are still returned, so that they can be cleaned up.
+    pub async fn entries(&self) -> anyhow::Result<Vec<(CacheKey, anyhow::Result<CacheEntry>)>> {
+        let mut raw_entries = vec![];
+        match &self.storage {
+            Storage::Files => {
+                let mut dir = tokio::fs::read_dir(&self.dir).await.context(format!("Failed to read {:?}. Do we have permissions?", self.dir))?;
+                while let Some(file) = dir.next_entry().await? {
+                    let path = file.path();
+                    // Entries never have an extension, unlike the SQLite database
+                    if !file.file_type().await?.is_file() || path.extension().is_some() {
+                        continue;
+                    }
+                    let bytes = tokio::fs::read(&path).await.context(format!("Failed to read {path:?}"));
+                    raw_entries.push((CacheKey::File(path), bytes));
+                }
+            }
+            Storage::Sqlite(connection) => {
+                let connection = connection.lock().unwrap();
+                let mut statement = connection.prepare("SELECT url, entry FROM entries")?;
+                let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                for row in rows {
+                    let (url, bytes): (String, Vec<u8>) = row?;
+                    raw_entries.push((CacheKey::Row(url), Ok(bytes)));
+                }
+            }
+        }
+
+        Ok(raw_entries
+            .into_iter()
+            .map(|(key, bytes)| {
+                let entry = bytes.and_then(|bytes| match CacheEntry::decode(&bytes) {
+                    Ok(Some(entry)) => Ok(entry),
+                    Ok(None) => Err(anyhow::anyhow!("{key:?} was written by a different version of resume-builder")),
+                    Err(e) => Err(e.context(format!("Failed to deserialize {key:?}")))
+                });
+                (key, entry)
+            })
+            .collect())
+    }
+
+    /// Deletes the entry stored under the given key
+    pub async fn remove_key(&self, key: &CacheKey) -> anyhow::Result<bool> {
+        match (&self.storage, key) {
+            (Storage::Files, CacheKey::File(path)) => match tokio::fs::remove_file(path).await {
+                Ok(()) => Ok(true),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+                Err(e) => Err(anyhow::Error::from(e).context(format!("Failed to delete {path:?}. Do we have permissions?")))
+            }
+            (Storage::Sqlite(connection), CacheKey::Row(url)) => {
+                let deleted = connection.lock().unwrap().execute("DELETE FROM entries WHERE url = ?1", [url])?;
+                Ok(deleted > 0)
+            }
+            _ => unreachable!("Cache keys should match the storage they came from")
+        }
+    }
+
+    /// Deletes the entry for the given website, returning false if it was not cached
+    pub async fn remove(&self, url: &Url) -> anyhow::Result<bool> {
+        self.remove_key(&self.key(url)).await
+    }
+
+    /// Writes the entry for the given website, replacing any existing entry
+    pub async fn insert(&self, url: &Url, entry: &CacheEntry) -> anyhow::Result<()> {
+        match &self.storage {
+            Storage::Files => {
+                let path = self.path(url);
+                // Written to a temporary file first so that other instances of resume-builder
+                // never read a partially written entry
+                let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+                tokio::fs::write(&temp_path, entry.encode()).await.context(format!("Failed to write {temp_path:?}. Do we have permissions?"))?;
+                tokio::fs::rename(&temp_path, &path).await.context(format!("Failed to replace {path:?}. Do we have permissions?"))
+            }
+            Storage::Sqlite(connection) => {
+                connection
+                    .lock()
+                    .unwrap()
+                    .execute(
+                        "INSERT OR REPLACE INTO entries (url, fetched_at, entry) VALUES (?1, ?2, ?3)",
+                        params![url.as_str(), entry.fetched_at as i64, entry.encode()]
+                    )
+                    .context(format!("Failed to write the cache entry for {url}"))?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes every readable entry into a zstd compressed tarball, along with an index of the URLs in it
+    ///
+    /// Returns the number of entries that were exported.
+    pub async fn export(&self, path: &Path) -> anyhow::Result<usize> {
+        let entries = self.entries().await?;
+        let file = std::fs::File::create(path).context(format!("Failed to create {path:?}. Do we have permissions?"))?;
+        let mut archive = tar::Builder::new(zstd::Encoder::new(file, 0)?.auto_finish());
+        let mut index = ExportIndex { entries: vec![] };
+
+        for (i, (_, entry)) in entries.into_iter().enumerate() {
+            let Ok(entry) = entry else { continue };
+            let file = format!("entries/{i}");
+            append_file(&mut archive, &file, &entry.encode())?;
+            index.entries.push(ExportedEntry { url: entry.url, file, fetched_at: entry.fetched_at });
+        }
+
+        append_file(&mut archive, EXPORT_INDEX_FILE_NAME, toml::to_string(&index)?.as_bytes())?;
+        archive.finish()?;
+        Ok(index.entries.len())
+    }
+
+    /// Reads a tarball written by `export` into this cache
+    ///
+    /// Existing entries are only replaced if the imported entry is newer. Returns the number of entries
+    /// that were imported.
+    pub async fn import(&self, path: &Path) -> anyhow::Result<usize> {
+        let mut files = FxHashMap::default();
+        let mut index = None;
+        {
+            let file = std::fs::File::open(path).context(format!("Failed to open {path:?}. Does it exist? Do we have permissions?"))?;
+            let mut archive = tar::Archive::new(zstd::Decoder::new(file)?);
+
+            for file in archive.entries()? {
+                let mut file = file?;
+                let name = file.path()?.to_string_lossy().into_owned();
+                let mut bytes = vec![];
+                file.read_to_end(&mut bytes)?;
+                if name == EXPORT_INDEX_FILE_NAME {
+                    index = Some(toml::from_str::<ExportIndex>(std::str::from_utf8(&bytes)?)?);
+                } else {
+                    files.insert(name, bytes);
+                }
+            }
+        }
+
+        let index = index.context(format!("{path:?} is missing its index. Was it made with `resume-builder cache export`?"))?;
+        let mut imported = 0;
+        for exported in index.entries {
+            let url: Url = exported.url.parse()?;
+            let bytes = files.get(&exported.file).context(format!("{path:?} is missing {}", exported.file))?;
+            let Some(entry) = CacheEntry::decode(bytes)? else {
+                return Err(anyhow::anyhow!("{path:?} was exported by a different version of resume-builder"))
+            };
+            if let Ok(Some(existing)) = self.get(&url).await {
+                if existing.fetched_at >= entry.fetched_at {
+                    continue;
+                }
+            }
+            self.insert(&url, &entry).await?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
+
+
+const EXPORT_INDEX_FILE_NAME: &str = "index.toml";
+
+
+/// Lists the entries in an exported cache
+#[derive(Serialize, Deserialize)]
+struct ExportIndex {
+    entries: Vec<ExportedEntry>
+}
+
+
+#[derive(Serialize, Deserialize)]
+struct ExportedEntry {
+    url: String,
+    /// The path of the entry in the tarball
+    file: String,
+    fetched_at: u64
+}
+
+
+fn append_file(archive: &mut tar::Builder<impl Write>, path: &str, bytes: &[u8]) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, path, bytes).map_err(Into::into)
+}
@@ -0,0 +1,168 @@
+//! Finding jobs on a company's own careers page, by following its links and reading its sitemap
+use std::collections::VecDeque;
+
+use anyhow::Context;
+use fxhash::FxHashSet;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use url::Url;
+
+use crate::job_boards::Posting;
+
+
+/// Links whose path looks like one of these are postings by default, such as `/careers/123-software-engineer`
+pub const DEFAULT_POSTING_REGEX: &str = r"/(jobs?|careers?|positions?|openings?|roles?|vacanc(y|ies))/[^/?#]*[0-9a-zA-Z][^/?#]*/?$";
+/// How many links away from the careers page postings are looked for by default
+pub const DEFAULT_MAX_DEPTH: usize = 2;
+/// The most pages of a careers site that are read, so that crawling a large site does not take forever
+const MAX_PAGES: usize = 50;
+
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CareerSiteEntry {
+    Url(Url),
+    Detailed {
+        url: Url,
+        posting_regex: Option<String>,
+        #[serde(default)]
+        title_keywords: Vec<String>,
+        max_depth: Option<usize>
+    }
+}
+
+
+/// A company's careers page, along with how its postings are found
+#[derive(Deserialize)]
+#[serde(try_from = "CareerSiteEntry")]
+pub struct CareerSite {
+    pub url: Url,
+    /// Links whose URLs match this regex are postings. Defaults to `DEFAULT_POSTING_REGEX`
+    pub posting_regex: Regex,
+    /// Only postings with at least one of these in their title (ignoring case) are wanted.
+    /// Every posting is wanted if empty
+    pub title_keywords: Vec<String>,
+    /// How many links away from `url` postings are looked for. Defaults to 2
+    pub max_depth: usize
+}
+
+
+impl TryFrom<CareerSiteEntry> for CareerSite {
+    type Error = anyhow::Error;
+
+    fn try_from(value: CareerSiteEntry) -> Result<Self, Self::Error> {
+        let (url, posting_regex, title_keywords, max_depth) = match value {
+            CareerSiteEntry::Url(url) => (url, None, vec![], None),
+            CareerSiteEntry::Detailed { url, posting_regex, title_keywords, max_depth } => (url, posting_regex, title_keywords, max_depth)
+        };
+        let posting_regex = Regex::new(posting_regex.as_deref().unwrap_or(DEFAULT_POSTING_REGEX))
+            .context(format!("The posting_regex of {url} is not a valid regex"))?;
+        Ok(Self { url, posting_regex, title_keywords, max_depth: max_depth.unwrap_or(DEFAULT_MAX_DEPTH) })
+    }
+}
+
+
+/// Every link in the given page, along with its text
+///
+/// Links to other websites are left out, as are links to files and to other parts of the same page.
+fn links(html: &str, page_url: &Url) -> Vec<(Url, String)> {
+    let html = Html::parse_document(html);
+    html.select(&Selector::parse("a[href]").unwrap())
+        .filter_map(|a| {
+            let mut url = page_url.join(a.value().attr("href")?).ok()?;
+            url.set_fragment(None);
+            if url.host_str() != page_url.host_str() || !matches!(url.scheme(), "http" | "https") {
+                return None
+            }
+            let text = a.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+            Some((url, text))
+        })
+        .collect()
+}
+
+
+/// Every URL in the given sitemap
+fn sitemap_urls(sitemap: &str) -> Vec<Url> {
+    let loc = Regex::new(r"<loc>\s*([^<\s]+)\s*</loc>").unwrap();
+    loc.captures_iter(sitemap)
+        .filter_map(|c| c[1].replace("&amp;", "&").parse().ok())
+        .collect()
+}
+
+
+/// A title for a posting that was only found in a sitemap, made from the end of its URL,
+/// such as "senior software engineer" for `/jobs/123-senior-software-engineer`
+fn title_from_url(url: &Url) -> String {
+    let slug = url.path_segments().and_then(|x| x.filter(|x| !x.is_empty()).last()).unwrap_or_default();
+    slug.split(['-', '_', '+'])
+        .filter(|x| !x.chars().all(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+
+impl CareerSite {
+    fn is_wanted(&self, title: &str, keywords: &[String]) -> bool {
+        let title = title.to_lowercase();
+        keywords.is_empty() || keywords.iter().any(|k| title.contains(k))
+    }
+
+    /// Lists every wanted posting on this careers site
+    ///
+    /// Pages are read without Chrome, so postings that are only listed by JavaScript are only found if they
+    /// are in the site's sitemap.
+    pub async fn postings(&self, client: &reqwest::Client) -> anyhow::Result<Vec<Posting>> {
+        let keywords: Vec<_> = self.title_keywords.iter().map(|x| x.to_lowercase()).collect();
+        let mut postings = vec![];
+        let mut found = FxHashSet::<Url>::default();
+        let mut visited = FxHashSet::<Url>::default();
+        let mut queue = VecDeque::from([(self.url.clone(), 0)]);
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if visited.len() >= MAX_PAGES {
+                tracing::warn!("Only read the first {MAX_PAGES} pages of {}", self.url);
+                break
+            }
+            if !visited.insert(url.clone()) {
+                continue
+            }
+            let html = match client.get(url.clone()).send().await.and_then(|x| x.error_for_status()) {
+                Ok(response) => response.text().await?,
+                // Only the careers page itself has to exist
+                Err(e) if depth > 0 => {
+                    tracing::debug!("Skipping {url}: {e}");
+                    continue
+                }
+                Err(e) => return Err(anyhow::Error::new(e).context(format!("Failed to read {url}")))
+            };
+            for (link, text) in links(&html, &url) {
+                if self.posting_regex.is_match(link.as_str()) {
+                    if found.insert(link.clone()) && self.is_wanted(&text, &keywords) {
+                        postings.push(Posting { url: link, title: text });
+                    }
+                } else if depth < self.max_depth && !visited.contains(&link) {
+                    queue.push_back((link, depth + 1));
+                }
+            }
+        }
+
+        // Sitemaps are optional, so failing to read one is not an error
+        let sitemap_url = self.url.join("/sitemap.xml")?;
+        match client.get(sitemap_url.clone()).send().await.and_then(|x| x.error_for_status()) {
+            Ok(response) => {
+                for url in sitemap_urls(&response.text().await?) {
+                    if url.host_str() != self.url.host_str() || !self.posting_regex.is_match(url.as_str()) || !found.insert(url.clone()) {
+                        continue
+                    }
+                    let title = title_from_url(&url);
+                    if self.is_wanted(&title, &keywords) {
+                        postings.push(Posting { url, title });
+                    }
+                }
+            }
+            Err(e) => tracing::debug!("Skipping {sitemap_url}: {e}")
+        }
+        Ok(postings)
+    }
+}
@@ -0,0 +1,294 @@
+//! What config.toml can contain
+use std::path::PathBuf;
+
+use anyhow::Context;
+use fxhash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    cache::CacheBackend,
+    career_sites::CareerSite,
+    email::EmailConfig,
+    failure::{failure_kind, FailureKind},
+    job_boards::CompanyBoard,
+    job_feeds::JobFeed,
+    job_filter::JobFilter,
+    keywords::KeywordExtractionConfig,
+    resume_gen::{backend::RenderBackend, language::TranslationBackend, layout::OutputLayout, paper::Paper, redact::Redaction, selection::{EducationOrder, Selection, Strategy}},
+    tab_pool::BrowserConfig,
+    upload::UploadConfig,
+    ResumeData
+};
+
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+
+/// A job posting to generate a resume for, along with options specific to it
+#[derive(Deserialize, Serialize, Clone)]
+pub struct JobWebsite {
+    pub url: Url,
+    /// The name of a template in `templates`, `"default"`, `"sidebar"`, or a path to a template.
+    /// Uses `resume_template_path` if not given
+    pub template: Option<String>,
+    /// The name of the folder the resume is written into. Defaults to the company and job title
+    pub output_name: Option<String>,
+    /// The language of the resume, such as "es". Uses `--language` or `resume_language` if not given
+    pub language: Option<String>
+}
+
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum JobWebsiteEntry {
+    Url(Url),
+    Detailed(JobWebsite)
+}
+
+
+impl From<JobWebsiteEntry> for JobWebsite {
+    fn from(value: JobWebsiteEntry) -> Self {
+        match value {
+            JobWebsiteEntry::Url(url) => Self { url, template: None, output_name: None, language: None },
+            JobWebsiteEntry::Detailed(website) => website
+        }
+    }
+}
+
+
+/// One of several resumes generated for every job, to find out which gets more responses
+#[derive(Deserialize)]
+pub struct Variant {
+    /// The name of a template in `templates`, `"default"`, `"sidebar"`, or a path to a template.
+    /// Uses the job's template if not given
+    pub template: Option<String>,
+    /// Put every note on the resume, after the ones relevant to the job
+    #[serde(default)]
+    pub all_notes: bool,
+    /// The most notes put under each education entry, most relevant first. Unlimited if not given
+    pub max_notes: Option<usize>
+}
+
+
+impl Variant {
+    pub fn select(&self, selection: Selection, resume_data: &ResumeData) -> Selection {
+        let mut selection = if self.all_notes { selection.with_all_notes(resume_data) } else { selection };
+        if let Some(max_notes) = self.max_notes {
+            selection.truncate_notes(max_notes);
+        }
+        selection
+    }
+}
+
+
+/// The folder of the variant at the given index, from `variant_a` to `variant_z`
+pub fn variant_name(index: usize) -> String {
+    format!("variant_{}", (b'a' + index as u8) as char)
+}
+
+
+#[derive(Deserialize, Default)]
+pub struct Config {
+    pub job_requirement_websites: Vec<JobWebsiteEntry>,
+    /// Boards whose open jobs are all added to `job_requirement_websites`, such as "greenhouse:stripe" or "lever:netflix"
+    #[serde(default)]
+    pub company_boards: Vec<CompanyBoard>,
+    /// Only jobs from `company_boards` whose titles match this regex are added
+    pub company_board_title_regex: Option<String>,
+    /// RSS or Atom feeds of jobs, either as URLs or like `{ url = "...", title_regex = "...", keywords = ["rust"] }`.
+    /// Items that were not in the feed on a previous run are added to `job_requirement_websites`
+    #[serde(default)]
+    pub job_feeds: Vec<JobFeed>,
+    /// Careers pages of companies, either as URLs or like `{ url = "...", posting_regex = "...", title_keywords = ["rust"], max_depth = 2 }`.
+    /// Links to postings are looked for on the page, the pages it links to and the site's sitemap, then added to
+    /// `job_requirement_websites`
+    #[serde(default)]
+    pub career_sites: Vec<CareerSite>,
+    /// Which jobs from `company_boards`, `job_feeds` and `career_sites` get resumes, such as
+    /// `{ include_title_regex = "(?i)engineer", exclude_title_regex = "(?i)senior|staff", required_keywords = ["rust"], locations = ["Remote", "Seattle"], min_salary = 100000 }`.
+    /// Jobs in `job_requirement_websites` always get resumes
+    #[serde(default)]
+    pub job_filter: JobFilter,
+    #[serde(default)]
+    pub omit_default_scrapers: Vec<String>,
+    #[serde(default)]
+    pub enable_optional_scrapers: Vec<String>,
+    /// The scrapers that also extract keywords from every sentence of paragraphs, instead of only from
+    /// list items, such as `["workday"]`
+    #[serde(default)]
+    pub paragraph_scrapers: FxHashSet<String>,
+    pub resume_data: ResumeData,
+    /// The language that `resume_data` is written in, and that resumes are in by default, such as "en" (the default).
+    /// Headings and dates are translated from a bundled list of languages
+    pub resume_language: Option<String>,
+    /// Translates `resume_data` for resumes in other languages than `resume_language`, such as
+    /// `{ backend = "deepl", api_key = "..." }` or `{ backend = "libretranslate", url = "https://..." }`.
+    /// Only headings and dates are translated if not given
+    pub translation_backend: Option<TranslationBackend>,
+    /// The LanguageTool server that `lint-resume` checks with. Defaults to the public one, which is rate limited
+    pub languagetool_url: Option<Url>,
+    /// The name of a template in `templates`, `"default"`, `"sidebar"`, or a path to a template.
+    /// Defaults to `"default"`
+    pub resume_template_path: Option<String>,
+    /// Templates that can be referred to by name from `job_requirement_websites`
+    #[serde(default)]
+    pub templates: FxHashMap<String, PathBuf>,
+    /// Moves sections of every template to the regions they mark with `<region-name>`, such as
+    /// `{ experience = "sidebar" }` to put experience in the narrow column of the sidebar template
+    #[serde(default)]
+    pub section_regions: FxHashMap<String, String>,
+    /// Where scraped pages are cached. Defaults to the platform's cache directory
+    pub cache_dir: Option<PathBuf>,
+    /// How many days a scraped page stays cached before it is scraped again.
+    /// Cached pages never expire if not given
+    pub cache_ttl_days: Option<f64>,
+    /// How scraped pages are stored. Either "files" (the default) or "sqlite"
+    #[serde(default)]
+    pub cache_backend: CacheBackend,
+    /// How many hours to wait before scraping a website again if nothing could be scraped from it.
+    /// Defaults to 24. Websites are always scraped again if the enabled scrapers have changed
+    pub no_data_retry_hours: Option<f64>,
+    /// How to be told when a run finishes
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Websites that can send jobs to `resume-builder daemon` from a bookmarklet, such as "https://www.linkedin.com".
+    /// Browser extensions can always send jobs
+    #[serde(default)]
+    pub daemon_allowed_origins: Vec<String>,
+    /// Cloud storage that resumes are uploaded to once they are generated, such as
+    /// `{ backend = "dropbox", access_token = "...", path = "/Resumes" }`
+    #[serde(default)]
+    pub uploads: Vec<UploadConfig>,
+    /// Email generated resumes, such as `{ to = "me@example.com", from = "me@example.com", transport = "sendmail" }`
+    pub email: Option<EmailConfig>,
+    /// How many times websites that failed to navigate or render are tried again at the end of a run. Defaults to 2
+    pub max_retries: Option<usize>,
+    /// Generate one resume for websites that are the same job on different job boards, with their keywords merged.
+    /// Rendering waits until every website has been scraped if enabled. Defaults to false
+    pub dedupe_jobs: Option<bool>,
+    /// Websites are skipped instead of getting an untailored resume if less than this much of their page data could be
+    /// scraped. The job title, company and keywords are each worth 0.3, and the description 0.1. Defaults to 0.9,
+    /// so that only the description can be missing
+    pub min_completeness: Option<f32>,
+    /// Keep the previous `resume.pdf` of a job as `resume_v<n>.pdf` when it is rendered again with different
+    /// resume data, template or keywords, as it may have already been submitted. Defaults to true
+    pub keep_old_resumes: Option<bool>,
+    /// Also render `base/resume.pdf` in `output_dir` on every run, with every note and nothing tailored to a job.
+    /// Defaults to true
+    pub base_resume: Option<bool>,
+    /// The most experience entries on each resume, so that it fits on one page without shrinking the text.
+    /// The ones most relevant to the job are chosen. There is no limit by default
+    pub max_experience_entries: Option<usize>,
+    /// The most bullets under each experience entry. Defaults to 3
+    pub max_bullets_per_entry: Option<usize>,
+    /// The order education entries are put on resumes in: `"reverse-chronological"` (the default) by when they ended,
+    /// `"config"` for the order they are written in, or `"relevance"` for the most relevant to the job first
+    #[serde(default)]
+    pub education_order: EducationOrder,
+    /// Leave out the least relevant education entries when a resume would not fit on one page otherwise,
+    /// after leaving out notes. At least one is always kept
+    #[serde(default)]
+    pub drop_education_entries: bool,
+    /// How notes, bullets and experience entries are chosen: `"greedy"` (the default) for the most keyword weight,
+    /// `"coverage"` for the most keywords that were not already chosen, or `"manual"` to ignore keywords and keep
+    /// the order written here
+    #[serde(default)]
+    pub selection_strategy: Strategy,
+    /// Company names to use instead of the ones scraped, such as `{ "acme_inc" = "Acme" }`, for when a website only
+    /// gives a slug or an internal brand name
+    #[serde(default)]
+    pub company_names: FxHashMap<String, String>,
+    /// Generate several resumes for every job, into `variant_a`, `variant_b` and so on inside its folder, such as
+    /// `[{}, { template = "compact", max_notes = 2 }]`. The first variant is the one that is uploaded, emailed
+    /// and recorded in applications.csv. Use `resume-builder diff` on two variant folders to see how they differ
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+    /// Also generate a version of every resume for public job boards into a `redacted` folder inside its folder,
+    /// such as `{ email_alias = "jobs@example.com" }`. The phone number is masked and the address left out by default
+    pub redaction: Option<Redaction>,
+    /// How many seconds navigating to and fetching a website can take before it fails, to be tried again later.
+    /// Defaults to 60
+    pub navigation_timeout_secs: Option<f64>,
+    /// How many seconds rendering a resume can take before it fails, to be tried again later. Defaults to 60
+    pub render_timeout_secs: Option<f64>,
+    /// What resumes are printed into PDFs with: `"chrome"` (the default), or `"wkhtmltopdf"` or `"weasyprint"` where
+    /// Chrome cannot run. Resumes are not fit onto one page without Chrome, and only some CSS is supported
+    #[serde(default)]
+    pub render_backend: RenderBackend,
+    /// The wkhtmltopdf or weasyprint executable to print with. Found on the PATH if not given
+    pub render_program: Option<PathBuf>,
+    /// How many idle Chrome tabs are kept open to be reused, for each of scraping and rendering. Defaults to 4
+    pub tab_pool_size: Option<usize>,
+    /// Connect to an already running Chrome instead of starting a new one
+    #[serde(default)]
+    pub browser: BrowserConfig,
+    /// How many keywords are extracted from each line of a job posting, and how
+    #[serde(default)]
+    pub keyword_extraction: KeywordExtractionConfig,
+    /// Websites on these hosts (or their subdomains) are fetched with a plain HTTP request instead of
+    /// through Chrome, as they do not need JavaScript. Such as "careers.example.com"
+    #[serde(default)]
+    pub static_hosts: Vec<String>,
+    /// Pages larger than this many bytes are cut down to this size before being scraped and cached.
+    /// Defaults to 8 MiB
+    pub max_html_bytes: Option<usize>,
+    /// Where resumes are written to. Defaults to a folder in the platform's documents directory
+    pub output_dir: Option<PathBuf>,
+    /// The paper resumes are printed on, such as `{ size = "a4", margin_inches = 0.5 }`. Resumes are fit inside
+    /// its margins. Defaults to `"letter"` with margins of 0.4 inches, as Chrome prints by default
+    #[serde(default)]
+    pub paper: Paper,
+    /// Also write a posting_annotated.html next to each resume, with the job's keywords highlighted by
+    /// whether the resume has them
+    #[serde(default)]
+    pub annotate_postings: bool,
+    /// How resumes are organized in `output_dir`: `"folders"` (the default), `"flat"`, `"by-company"` or `"by-date"`
+    #[serde(default)]
+    pub output_layout: OutputLayout,
+    /// The maximum number of websites that can be navigated to and scraped at the same time.
+    /// Unlimited if not given
+    pub max_concurrent_scrapes: Option<usize>,
+    /// The maximum number of resumes that can be rendered at the same time.
+    /// Unlimited if not given
+    pub max_concurrent_renders: Option<usize>
+}
+
+
+impl Config {
+    pub fn read() -> anyhow::Result<Self> {
+        let config = std::fs::read_to_string(CONFIG_PATH).context("Failed to read config.toml. Does it exist? Do we have permissions? Consider running `resume-builder wizard`").map_err(failure_kind(FailureKind::Config))?;
+        toml::from_str(&config).context("config.toml is invalid").map_err(failure_kind(FailureKind::Config))
+    }
+
+    /// Every file that affects the generated resumes, other than the cache
+    pub fn source_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(CONFIG_PATH)];
+        paths.extend(self.templates.values().cloned());
+        let website_templates = self.job_requirement_websites.iter().filter_map(|website| match website {
+            JobWebsiteEntry::Detailed(JobWebsite { template, .. }) => template.as_ref(),
+            _ => None
+        });
+        let templates = self.resume_template_path
+            .iter()
+            .chain(website_templates)
+            .chain(self.variants.iter().filter_map(|x| x.template.as_ref()));
+        for template in templates {
+            // Built in templates and ones in `templates` are not paths
+            if !matches!(template.as_str(), "default" | "sidebar") && !self.templates.contains_key(template) {
+                paths.push(template.into());
+            }
+        }
+        paths
+    }
+}
+
+
+#[derive(Deserialize, Default)]
+pub struct NotificationConfig {
+    /// Show a desktop notification
+    #[serde(default)]
+    pub desktop: bool,
+    /// POST a summary to this URL, such as a Slack or Discord webhook
+    pub webhook_url: Option<Url>
+}
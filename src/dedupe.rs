@@ -0,0 +1,83 @@
+//! Recognizing the same job posted on several job boards, such as on LinkedIn, Simplify, and the company's Workday
+use fxhash::FxHashSet;
+use url::Url;
+
+use crate::page_scrapers::PageData;
+
+
+/// Words at the end of company names that differ between job boards
+const COMPANY_SUFFIXES: [&str; 9] = ["inc", "incorporated", "llc", "ltd", "limited", "corp", "corporation", "co", "company"];
+/// How similar the words of two job titles must be, from 0 to 1, for them to be the same job
+const TITLE_SIMILARITY: f32 = 0.8;
+
+
+/// Lowercases the given text and splits it into words, treating any punctuation or underscores as spaces
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(str::to_lowercase)
+}
+
+
+/// The words of a company name, without suffixes like "Inc." that differ between job boards
+pub fn normalize_company(company: &str) -> String {
+    let mut words: Vec<_> = words(company).collect();
+    while words.len() > 1 && words.last().is_some_and(|x| COMPANY_SUFFIXES.contains(&x.as_str())) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+
+/// The given job title without anything in brackets, which is usually a location or a requisition ID
+pub(crate) fn strip_brackets(title: &str) -> String {
+    let mut depth = 0usize;
+    title
+        .chars()
+        .filter(|&c| {
+            match c {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth = depth.saturating_sub(1),
+                _ => return depth == 0
+            }
+            false
+        })
+        .collect()
+}
+
+
+/// The words of a job title, without anything in brackets
+pub(crate) fn title_words(title: &str) -> FxHashSet<String> {
+    words(&strip_brackets(title)).collect()
+}
+
+
+/// Parts of a URL that look like the ID of a job, such as the `R12345` in `.../Software-Engineer_R12345`
+///
+/// Job boards that repost a job often keep the company's own requisition ID.
+fn job_ids(url: &Url) -> FxHashSet<String> {
+    url.path_segments()
+        .into_iter()
+        .flatten()
+        .flat_map(words)
+        .chain(url.query_pairs().flat_map(|(_, v)| words(&v).collect::<Vec<_>>()))
+        .filter(|x| x.len() >= 5 && x.chars().any(|c| c.is_ascii_digit()))
+        .collect()
+}
+
+
+/// Whether the two websites are likely to be the same job
+///
+/// They must be from the same company, and have either a job ID in common or very similar titles.
+pub fn is_same_job(a: &PageData, b: &PageData) -> bool {
+    if a.company.is_empty() || normalize_company(&a.company) != normalize_company(&b.company) {
+        return false
+    }
+    if !job_ids(&a.url).is_disjoint(&job_ids(&b.url)) {
+        return true
+    }
+
+    let (a, b) = (title_words(&a.job_title), title_words(&b.job_title));
+    let union = a.union(&b).count();
+    union > 0 && a.intersection(&b).count() as f32 / union as f32 >= TITLE_SIMILARITY
+}
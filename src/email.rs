@@ -0,0 +1,125 @@
+//! Emailing generated resumes, through SMTP or sendmail
+use std::path::PathBuf;
+
+use anyhow::Context;
+use lettre::{
+    message::{header::ContentType, Attachment, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    AsyncSendmailTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor
+};
+use serde::Deserialize;
+
+
+#[derive(Deserialize)]
+pub struct EmailConfig {
+    /// Who the resumes are sent to, such as "me@example.com"
+    pub to: String,
+    /// Who the resumes are sent from, such as "Resume Builder <me@example.com>"
+    pub from: String,
+    /// Send every resume of a run in one email at the end, instead of one email per resume
+    #[serde(default)]
+    pub digest: bool,
+    #[serde(flatten)]
+    pub transport: EmailTransport
+}
+
+
+/// How emails are sent, picked with `transport`
+#[derive(Deserialize)]
+#[serde(tag = "transport", rename_all = "snake_case")]
+pub enum EmailTransport {
+    Smtp {
+        host: String,
+        /// Defaults to 465, with TLS. Any other port uses STARTTLS
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>
+    },
+    /// Uses the sendmail command of this machine
+    Sendmail {
+        /// Defaults to `sendmail`
+        command: Option<String>
+    }
+}
+
+
+/// A generated resume to email
+pub struct EmailedResume {
+    pub url: String,
+    pub company: String,
+    pub job_title: String,
+    /// From 0 to 1
+    pub match_score: f32,
+    pub path: PathBuf
+}
+
+
+impl EmailedResume {
+    fn describe(&self) -> String {
+        format!("{} at {}\n{}\nMatch score: {:.1}%\n", self.job_title, self.company, self.url, self.match_score * 100.0)
+    }
+
+    async fn attachment(&self) -> anyhow::Result<SinglePart> {
+        let resume = tokio::fs::read(&self.path).await.context(format!("Failed to read {:?}. Do we have permissions?", self.path))?;
+        let file_name = format!("{} {} resume.pdf", self.company, self.job_title).replace(['/', '\\'], "-");
+        Ok(Attachment::new(file_name).body(resume, ContentType::parse("application/pdf").unwrap()))
+    }
+}
+
+
+impl EmailConfig {
+    fn message(&self, subject: String, body: String, attachments: Vec<SinglePart>) -> anyhow::Result<Message> {
+        let multipart = attachments.into_iter().fold(MultiPart::mixed().singlepart(SinglePart::plain(body)), MultiPart::singlepart);
+        Message::builder()
+            .from(self.from.parse().context(format!("{:?} is not a valid email address", self.from))?)
+            .to(self.to.parse().context(format!("{:?} is not a valid email address", self.to))?)
+            .subject(subject)
+            .multipart(multipart)
+            .map_err(Into::into)
+    }
+
+    async fn send_message(&self, message: Message) -> anyhow::Result<()> {
+        match &self.transport {
+            EmailTransport::Smtp { host, port, username, password } => {
+                let mut transport = match port {
+                    None | Some(465) => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+                    Some(port) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?.port(*port)
+                };
+                if let (Some(username), Some(password)) = (username, password) {
+                    transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+                }
+                transport.build().send(message).await.context(format!("Failed to send an email through {host}"))?;
+            }
+            EmailTransport::Sendmail { command } => {
+                let transport = match command {
+                    Some(command) => AsyncSendmailTransport::<Tokio1Executor>::new_with_command(command),
+                    None => AsyncSendmailTransport::<Tokio1Executor>::new()
+                };
+                transport.send(message).await.context("Failed to send an email through sendmail")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Emails the given resumes, either one at a time or as a digest
+    pub async fn send(&self, resumes: &[EmailedResume]) -> anyhow::Result<()> {
+        if resumes.is_empty() {
+            return Ok(())
+        }
+        if self.digest {
+            let mut attachments = vec![];
+            for resume in resumes {
+                attachments.push(resume.attachment().await?);
+            }
+            let body = resumes.iter().map(EmailedResume::describe).collect::<Vec<_>>().join("\n");
+            let message = self.message(format!("{} resumes generated", resumes.len()), body, attachments)?;
+            return self.send_message(message).await
+        }
+
+        for resume in resumes {
+            let message = self.message(format!("Resume for {} at {}", resume.job_title, resume.company), resume.describe(), vec![resume.attachment().await?])?;
+            self.send_message(message).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! How errors are classified, to decide the exit code and which websites are tried again
+use crate::{resume_gen::FontTooSmall, tab_pool::BrowserUnavailable};
+
+
+/// The kinds of errors that have their own exit code, as listed in `resume-builder --help`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    Config = 2,
+    Template = 3,
+    Browser = 4,
+    Scrape = 5,
+    Render = 6
+}
+
+
+impl FailureKind {
+    /// When websites fail in different ways, the kind with the highest priority decides the exit code
+    pub fn priority(self) -> u8 {
+        match self {
+            FailureKind::Browser => 4,
+            FailureKind::Config => 3,
+            FailureKind::Template => 2,
+            FailureKind::Render => 1,
+            FailureKind::Scrape => 0
+        }
+    }
+}
+
+
+/// Wraps an error with how it should be handled
+#[derive(Debug)]
+pub struct ClassifiedError {
+    error: anyhow::Error,
+    pub kind: Option<FailureKind>,
+    /// Whether the error might not happen again if the website is tried again, such as navigation timeouts or Chrome crashing
+    pub transient: bool
+}
+
+
+impl std::fmt::Display for ClassifiedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+
+impl std::error::Error for ClassifiedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+
+fn classify(error: anyhow::Error, f: impl FnOnce(&mut ClassifiedError)) -> anyhow::Error {
+    let mut classified = match error.downcast::<ClassifiedError>() {
+        Ok(classified) => classified,
+        Err(error) => ClassifiedError { error, kind: None, transient: false }
+    };
+    f(&mut classified);
+    classified.into()
+}
+
+
+pub fn classification(error: &anyhow::Error) -> Option<&ClassifiedError> {
+    error.chain().find_map(|x| x.downcast_ref::<ClassifiedError>())
+}
+
+
+pub fn transient(error: anyhow::Error) -> anyhow::Error {
+    classify(error, |x| x.transient = true)
+}
+
+
+/// Gives the error the given kind, unless it already has one, which would be more specific
+pub fn failure_kind(kind: FailureKind) -> impl FnOnce(anyhow::Error) -> anyhow::Error {
+    move |error| classify(error, |x| { x.kind.get_or_insert(kind); })
+}
+
+
+/// Rendering errors are worth retrying, unless the template itself is the problem
+pub fn render_error(error: anyhow::Error) -> anyhow::Error {
+    if error.downcast_ref::<FontTooSmall>().is_some() {
+        failure_kind(FailureKind::Template)(error)
+    } else {
+        transient(error)
+    }
+}
+
+
+/// Errors from acquiring a tab are only worth retrying if Chrome is running
+pub fn tab_error(error: anyhow::Error) -> anyhow::Error {
+    if error.downcast_ref::<BrowserUnavailable>().is_some() {
+        failure_kind(FailureKind::Browser)(error)
+    } else {
+        transient(error)
+    }
+}
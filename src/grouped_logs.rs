@@ -0,0 +1,85 @@
+//! Holds back the log lines of each website, so that they can be printed together once it is finished,
+//! instead of being interleaved with the lines of every other website being processed at the same time
+use std::{cell::RefCell, fmt::Debug, sync::Mutex};
+
+use fxhash::FxHashMap;
+use tracing::{field::{Field, Visit}, span::{Attributes, Id}, Event, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+
+/// The log lines held back for each website, in the order they were logged
+static BUFFERS: Mutex<Option<FxHashMap<String, Vec<u8>>>> = Mutex::new(None);
+
+thread_local! {
+    /// The website of the event that is being logged on this thread, if it was logged inside one of its spans
+    static CURRENT_URL: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+
+/// The `url` field of a span, stored in its extensions
+struct SpanUrl(String);
+
+
+/// Finds the `url` field of a span
+struct UrlVisitor(Option<String>);
+
+
+impl Visit for UrlVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "url" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "url" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+
+/// Remembers which website each event was logged for, so that `hold_back` knows where its line goes
+///
+/// Must be added to the subscriber before the layer that writes the lines, so that it sees each event first.
+pub struct UrlLayer;
+
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for UrlLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = UrlVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(url), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(SpanUrl(url));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let url = ctx
+            .event_scope(event)
+            .and_then(|scope| scope.from_root().find_map(|span| span.extensions().get::<SpanUrl>().map(|x| x.0.clone())));
+        CURRENT_URL.with(|x| *x.borrow_mut() = url);
+    }
+}
+
+
+/// Holds back the given log line if it was logged for a website with `UrlLayer`, returning whether it was
+pub fn hold_back(line: &[u8]) -> bool {
+    CURRENT_URL.with(|url| {
+        let Some(url) = &*url.borrow() else { return false };
+        BUFFERS.lock().unwrap().get_or_insert_with(Default::default).entry(url.clone()).or_default().extend_from_slice(line);
+        true
+    })
+}
+
+
+/// Every log line that was held back for the given website
+pub fn take(url: &str) -> Option<Vec<u8>> {
+    BUFFERS.lock().unwrap().as_mut().and_then(|x| x.remove(url))
+}
+
+
+/// Every log line that was held back for any website that has not been taken yet, by website
+pub fn take_all() -> Vec<(String, Vec<u8>)> {
+    BUFFERS.lock().unwrap().take().unwrap_or_default().into_iter().collect()
+}
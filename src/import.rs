@@ -0,0 +1,94 @@
+//! Adding job URLs exported from other job trackers to `job_requirement_websites`
+use std::path::Path;
+
+use anyhow::Context;
+use url::Url;
+
+use crate::{applications::parse_csv, cache::canonicalize_url};
+
+
+/// Whether a column or field with this name holds the link to a job posting
+fn is_link_field(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["url", "link", "href"].iter().any(|x| name.contains(x))
+}
+
+
+fn parse_job_url(text: &str) -> Option<Url> {
+    let url: Url = text.trim().parse().ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+}
+
+
+/// Collects the links in every field of the given JSON whose name looks like it holds a link
+fn json_urls(value: &serde_json::Value, urls: &mut Vec<Url>) {
+    match value {
+        serde_json::Value::Array(values) => values.iter().for_each(|x| json_urls(x, urls)),
+        serde_json::Value::Object(fields) => for (name, value) in fields {
+            match value {
+                serde_json::Value::String(text) if is_link_field(name) => urls.extend(parse_job_url(text)),
+                _ => json_urls(value, urls)
+            }
+        }
+        _ => {}
+    }
+}
+
+
+/// Reads the job URLs out of the tracked jobs exported from Simplify, as either CSV or JSON
+///
+/// Any column or field whose name has "url" or "link" in it is read, so that changes to the
+/// export format are less likely to break this. Duplicates are removed.
+pub fn simplify_export_urls(export: &str) -> anyhow::Result<Vec<Url>> {
+    let mut urls = vec![];
+    if export.trim_start().starts_with(['[', '{']) {
+        let export: serde_json::Value = serde_json::from_str(export).context("The Simplify export is not valid JSON")?;
+        json_urls(&export, &mut urls);
+    } else {
+        let mut rows = parse_csv(export).into_iter();
+        let header = rows.next().context("The Simplify export is empty")?;
+        let link_columns: Vec<_> = header.iter().enumerate().filter(|(_, name)| is_link_field(name)).map(|(i, _)| i).collect();
+        if link_columns.is_empty() {
+            return Err(anyhow::anyhow!("The Simplify export has no column with links to jobs. The columns are {header:?}"))
+        }
+        for row in rows {
+            urls.extend(link_columns.iter().filter_map(|&i| row.get(i)).filter_map(|x| parse_job_url(x)));
+        }
+    }
+
+    let mut seen = fxhash::FxHashSet::default();
+    urls.retain(|url| seen.insert(canonicalize_url(url)));
+    Ok(urls)
+}
+
+
+/// Adds the given URLs to `job_requirement_websites` in the config at the given path, skipping those that are already in it
+///
+/// Returns how many were added.
+pub fn add_to_config(config_path: &Path, urls: Vec<Url>) -> anyhow::Result<usize> {
+    let config = std::fs::read_to_string(config_path).context(format!("Failed to read {config_path:?}. Does it exist? Do we have permissions? Consider running `resume-builder wizard`"))?;
+    let mut config: toml::Table = toml::from_str(&config)?;
+    let websites = config
+        .entry("job_requirement_websites")
+        .or_insert_with(|| toml::Value::Array(vec![]))
+        .as_array_mut()
+        .context("job_requirement_websites is not a list")?;
+
+    // Entries are either URLs or tables with a url
+    let mut existing: fxhash::FxHashSet<Url> = websites
+        .iter()
+        .filter_map(|x| x.as_str().or_else(|| x.get("url")?.as_str()))
+        .filter_map(|x| x.parse().ok())
+        .map(|x| canonicalize_url(&x))
+        .collect();
+    let mut added = 0;
+    for url in urls {
+        if existing.insert(canonicalize_url(&url)) {
+            websites.push(toml::Value::String(url.to_string()));
+            added += 1;
+        }
+    }
+
+    std::fs::write(config_path, toml::to_string_pretty(&config)?).context(format!("Failed to write {config_path:?}. Do we have permissions?"))?;
+    Ok(added)
+}
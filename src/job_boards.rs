@@ -0,0 +1,109 @@
+//! Listing every open job of a company through the public APIs of the job boards they use
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::Context;
+use serde::Deserialize;
+use url::Url;
+
+
+/// A company's job board, written as `<provider>:<company>` such as `greenhouse:stripe` or `lever:netflix`
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "String")]
+pub enum CompanyBoard {
+    Greenhouse(String),
+    Lever(String)
+}
+
+
+impl FromStr for CompanyBoard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (provider, company) = s.split_once(':').context(format!("{s:?} is not a valid company board. Company boards are written like \"greenhouse:stripe\""))?;
+        match provider {
+            "greenhouse" => Ok(Self::Greenhouse(company.into())),
+            "lever" => Ok(Self::Lever(company.into())),
+            _ => Err(anyhow::anyhow!("{provider:?} is not a supported job board. Only greenhouse and lever are supported"))
+        }
+    }
+}
+
+
+impl TryFrom<String> for CompanyBoard {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+
+impl Display for CompanyBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greenhouse(company) => write!(f, "greenhouse:{company}"),
+            Self::Lever(company) => write!(f, "lever:{company}")
+        }
+    }
+}
+
+
+/// An open job on a company board
+pub struct Posting {
+    pub url: Url,
+    pub title: String
+}
+
+
+#[derive(Deserialize)]
+struct GreenhouseJobs {
+    jobs: Vec<GreenhouseJob>
+}
+
+#[derive(Deserialize)]
+struct GreenhouseJob {
+    id: u64,
+    title: String
+}
+
+#[derive(Deserialize)]
+struct LeverPosting {
+    text: String,
+    #[serde(rename = "hostedUrl")]
+    hosted_url: Url
+}
+
+
+impl CompanyBoard {
+    /// Lists every open job on this board
+    pub async fn postings(&self, client: &reqwest::Client) -> anyhow::Result<Vec<Posting>> {
+        match self {
+            Self::Greenhouse(company) => {
+                let jobs: GreenhouseJobs = client
+                    .get(format!("https://boards-api.greenhouse.io/v1/boards/{company}/jobs"))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context(format!("Failed to list the jobs of {self}"))?;
+                // `absolute_url` is often the company's own careers page, which the greenhouse scraper cannot read
+                jobs.jobs
+                    .into_iter()
+                    .map(|job| Ok(Posting { url: format!("https://boards.greenhouse.io/{company}/jobs/{}", job.id).parse()?, title: job.title }))
+                    .collect()
+            }
+            Self::Lever(company) => {
+                let postings: Vec<LeverPosting> = client
+                    .get(format!("https://api.lever.co/v0/postings/{company}?mode=json"))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context(format!("Failed to list the jobs of {self}"))?;
+                Ok(postings.into_iter().map(|x| Posting { url: x.hosted_url, title: x.text }).collect())
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Finding jobs through RSS and Atom feeds, which many job boards and Google Alerts provide
+use anyhow::Context;
+use regex::Regex;
+use serde::Deserialize;
+use url::Url;
+
+use crate::job_boards::Posting;
+
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JobFeedEntry {
+    Url(Url),
+    Detailed {
+        url: Url,
+        title_regex: Option<String>,
+        #[serde(default)]
+        keywords: Vec<String>
+    }
+}
+
+
+/// An RSS or Atom feed of job postings, along with which of its items are wanted
+#[derive(Deserialize)]
+#[serde(try_from = "JobFeedEntry")]
+pub struct JobFeed {
+    pub url: Url,
+    /// Only items whose titles match this regex are wanted
+    pub title_regex: Option<Regex>,
+    /// Only items with at least one of these in their title or summary (ignoring case) are wanted.
+    /// Every item is wanted if empty
+    pub keywords: Vec<String>
+}
+
+
+impl TryFrom<JobFeedEntry> for JobFeed {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JobFeedEntry) -> Result<Self, Self::Error> {
+        Ok(match value {
+            JobFeedEntry::Url(url) => Self { url, title_regex: None, keywords: vec![] },
+            JobFeedEntry::Detailed { url, title_regex, keywords } => {
+                let title_regex = title_regex
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .context(format!("The title_regex of {url} is not a valid regex"))?;
+                Self { url, title_regex, keywords }
+            }
+        })
+    }
+}
+
+
+/// Google Alerts links to results through a redirect, with the actual link in the `url` parameter
+fn unwrap_redirect(url: Url) -> Url {
+    if url.host_str().is_some_and(|x| x.ends_with("google.com")) && url.path() == "/url" {
+        if let Some(target) = url.query_pairs().find(|(k, _)| k == "url").and_then(|(_, v)| v.parse().ok()) {
+            return target
+        }
+    }
+    url
+}
+
+
+impl JobFeed {
+    /// Lists every wanted item in this feed
+    pub async fn postings(&self, client: &reqwest::Client) -> anyhow::Result<Vec<Posting>> {
+        let keywords: Vec<_> = self.keywords.iter().map(|x| x.to_lowercase()).collect();
+
+        let body = client.get(self.url.clone()).send().await?.error_for_status()?.bytes().await?;
+        let feed = feed_rs::parser::parse(body.as_ref()).context(format!("{} is not a valid RSS or Atom feed", self.url))?;
+
+        Ok(feed.entries
+            .into_iter()
+            .filter_map(|entry| {
+                let url = unwrap_redirect(entry.links.first()?.href.parse().ok()?);
+                let title = entry.title.map(|x| x.content).unwrap_or_default();
+                if self.title_regex.as_ref().is_some_and(|x| !x.is_match(&title)) {
+                    return None
+                }
+                if !keywords.is_empty() {
+                    let text = format!("{title}\n{}", entry.summary.map(|x| x.content).unwrap_or_default()).to_lowercase();
+                    if !keywords.iter().any(|k| text.contains(k)) {
+                        return None
+                    }
+                }
+                Some(Posting { url, title })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,100 @@
+//! Which jobs found through company boards, feeds and careers sites are worth a resume
+use anyhow::Context;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{page_scrapers::salary::SalaryRange, PageData};
+
+
+#[derive(Deserialize)]
+struct JobFilterEntry {
+    include_title_regex: Option<String>,
+    exclude_title_regex: Option<String>,
+    #[serde(default)]
+    required_keywords: Vec<String>,
+    #[serde(default)]
+    locations: Vec<String>,
+    min_salary: Option<f64>
+}
+
+
+/// Filters on jobs that were found instead of given, such as `{ exclude_title_regex = "(?i)senior|staff", min_salary = 100000 }`
+///
+/// Titles are checked as soon as a job is found. Everything else is only known once the job is scraped,
+/// so jobs that fail those are scraped but do not get a resume.
+#[derive(Deserialize, Default)]
+#[serde(try_from = "JobFilterEntry")]
+pub struct JobFilter {
+    /// Only jobs whose titles match this regex are wanted
+    pub include_title_regex: Option<Regex>,
+    /// Jobs whose titles match this regex are not wanted
+    pub exclude_title_regex: Option<Regex>,
+    /// Only jobs with every one of these as a keyword or in their description (ignoring case) are wanted
+    pub required_keywords: Vec<String>,
+    /// Only jobs with one of these in their location (ignoring case), such as "Remote" or "Seattle", are wanted.
+    /// Jobs whose location could not be scraped are kept
+    pub locations: Vec<String>,
+    /// Only jobs that pay up to at least this much a year are wanted. Jobs without a pay range are kept
+    pub min_salary: Option<f64>
+}
+
+
+impl TryFrom<JobFilterEntry> for JobFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JobFilterEntry) -> Result<Self, Self::Error> {
+        let regex = |regex: Option<String>, name: &str| regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context(format!("job_filter.{name} is not a valid regex"));
+        Ok(Self {
+            include_title_regex: regex(value.include_title_regex, "include_title_regex")?,
+            exclude_title_regex: regex(value.exclude_title_regex, "exclude_title_regex")?,
+            required_keywords: value.required_keywords.iter().map(|x| x.to_lowercase()).collect(),
+            locations: value.locations.iter().map(|x| x.to_lowercase()).collect(),
+            min_salary: value.min_salary
+        })
+    }
+}
+
+
+impl JobFilter {
+    /// Whether a job with the given title is wanted
+    pub fn wants_title(&self, title: &str) -> bool {
+        self.include_title_regex.as_ref().map_or(true, |x| x.is_match(title)) &&
+            !self.exclude_title_regex.as_ref().is_some_and(|x| x.is_match(title))
+    }
+
+    /// Why the given scraped job is not wanted, if it is not
+    pub fn rejection(&self, page_data: &PageData) -> Option<String> {
+        if !self.wants_title(&page_data.job_title) {
+            return Some(format!("Its title {:?} is filtered out", page_data.job_title))
+        }
+
+        let description = page_data.description.to_lowercase();
+        let missing: Vec<_> = self.required_keywords
+            .iter()
+            .filter(|k| !description.contains(k.as_str()) && !page_data.keywords.iter().any(|x| x.key.eq_ignore_ascii_case(k)))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Some(format!("It does not mention {}", missing.join(", ")))
+        }
+
+        if let Some(location) = &page_data.location {
+            let lowercase = location.to_lowercase();
+            if !self.locations.is_empty() && !self.locations.iter().any(|x| lowercase.contains(x)) {
+                return Some(format!("Its location {location:?} is not one of job_filter.locations"))
+            }
+        }
+
+        let salary = page_data.salary.as_deref().and_then(|x| SalaryRange::parse(x).map(|range| (x, range)));
+        if let (Some(min_salary), Some((salary, range))) = (self.min_salary, salary) {
+            if range.max < min_salary {
+                return Some(format!("It pays {salary}, which is less than {min_salary} a year"))
+            }
+        }
+        None
+    }
+}
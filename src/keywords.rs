@@ -0,0 +1,202 @@
+use std::{sync::{mpsc::{self, SyncSender}, Mutex}, time::{Duration, Instant}};
+
+use serde::Deserialize;
+use tokio_rayon::rayon;
+
+
+/// A keyword extracted from a line of text
+#[derive(Debug, Clone)]
+pub struct Keyword {
+    pub text: String,
+    /// How representative this keyword is of the line it came from, from 0 to 1
+    pub score: f32
+}
+
+
+/// How keywords are extracted from each line of a job posting
+///
+/// Websites that are already cached keep the keywords they were scraped with.
+#[derive(Deserialize, Default, Clone, PartialEq)]
+pub struct KeywordExtractionConfig {
+    /// The most keywords extracted from each line. Defaults to 5
+    pub num_keywords: Option<usize>,
+    /// How different the keywords of each line should be from each other, from 0 to 1, with maximal
+    /// marginal relevance. Leave out to rank keywords only by how relevant they are to the line.
+    /// Only used with the `ml` feature
+    pub diversity: Option<f64>,
+    /// The fewest and most words in each keyword, such as `[1, 2]`. Defaults to `[1, 1]`.
+    /// Only used with the `ml` feature
+    pub ngram_range: Option<(usize, usize)>
+}
+
+
+/// A handle to a keyword extraction model that runs on its own thread
+///
+/// Cloning this handle does not load another model.
+#[derive(Clone)]
+pub struct KeywordExtractor {
+    sender: mpsc::Sender<(Vec<String>, SyncSender<(Vec<Vec<Keyword>>, Duration)>)>
+}
+
+
+/// Keywords that are still being extracted by a `KeywordExtractor`
+pub struct PendingKeywords<'a> {
+    receiver: mpsc::Receiver<(Vec<Vec<Keyword>>, Duration)>,
+    /// Where the time the model spent on these keywords is added to
+    time: Option<&'a Mutex<Duration>>
+}
+
+
+impl PendingKeywords<'_> {
+    /// Blocks until the keywords have been extracted
+    pub fn get(self) -> Vec<Vec<Keyword>> {
+        let (keywords, elapsed) = self.receiver.recv().unwrap();
+        if let Some(time) = self.time {
+            *time.lock().unwrap() += elapsed;
+        }
+        keywords
+    }
+}
+
+
+/// Extracts keywords with BERT, through rust-bert
+#[cfg(feature = "ml")]
+struct Model(rust_bert::pipelines::keywords_extraction::KeywordExtractionModel<'static>);
+
+
+#[cfg(feature = "ml")]
+impl Model {
+    fn load(config: KeywordExtractionConfig) -> Self {
+        use rust_bert::pipelines::keywords_extraction::{KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType};
+
+        let mut model_config = KeywordExtractionConfig::default();
+        if let Some(num_keywords) = config.num_keywords {
+            model_config.num_keywords = num_keywords;
+        }
+        if let Some(diversity) = config.diversity {
+            model_config.scorer_type = KeywordScorerType::MaximalMarginRelevance;
+            model_config.diversity = Some(diversity);
+        }
+        if let Some(ngram_range) = config.ngram_range {
+            model_config.ngram_range = ngram_range;
+        }
+        Self(KeywordExtractionModel::new(model_config).expect("Keyword Extraction Model should have initialized"))
+    }
+
+    fn predict(&self, lines: &[String]) -> Vec<Vec<Keyword>> {
+        self.0
+            .predict(lines)
+            .expect("Keyword Extraction Model should have worked")
+            .into_iter()
+            .map(|keywords| keywords.into_iter().map(|x| Keyword { text: x.text, score: x.score }).collect())
+            .collect()
+    }
+}
+
+
+/// Extracts keywords by how often words appear in a line, ignoring common english words
+///
+/// Used when resume-builder is built without the `ml` feature. It is far less accurate than BERT,
+/// but needs no libtorch.
+#[cfg(not(feature = "ml"))]
+struct Model {
+    keywords_per_line: usize
+}
+
+
+#[cfg(not(feature = "ml"))]
+impl Model {
+    /// The most keywords extracted from a single line by default, matching the default of rust-bert
+    const KEYWORDS_PER_LINE: usize = 5;
+    const STOP_WORDS: [&'static str; 95] = [
+        "a", "about", "above", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+        "because", "been", "before", "being", "between", "both", "but", "by", "can", "could", "did", "do",
+        "does", "doing", "during", "each", "etc", "few", "for", "from", "further", "had", "has", "have",
+        "having", "he", "her", "here", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its",
+        "just", "may", "me", "more", "most", "must", "my", "no", "nor", "not", "of", "on", "once", "only",
+        "or", "other", "our", "out", "over", "own", "same", "she", "should", "so", "some", "such", "than",
+        "that", "the", "their", "them", "then", "there", "these", "they", "this", "those", "through", "to",
+        "we", "will", "with", "you"
+    ];
+
+    fn load(config: KeywordExtractionConfig) -> Self {
+        Self { keywords_per_line: config.num_keywords.unwrap_or(Self::KEYWORDS_PER_LINE) }
+    }
+
+    fn predict(&self, lines: &[String]) -> Vec<Vec<Keyword>> {
+        lines
+            .iter()
+            .map(|line| {
+                let mut counts = fxhash::FxHashMap::<String, usize>::default();
+                let mut total = 0usize;
+                // '+' and '#' are kept for words like C++ and C#
+                for word in line.split(|c: char| !c.is_alphanumeric() && c != '+' && c != '#') {
+                    let word = word.to_lowercase();
+                    if word.len() < 2 || word.chars().all(|c| c.is_numeric()) || Self::STOP_WORDS.contains(&word.as_str()) {
+                        continue;
+                    }
+                    *counts.entry(word).or_default() += 1;
+                    total += 1;
+                }
+
+                let mut keywords: Vec<_> = counts
+                    .into_iter()
+                    .map(|(text, count)| Keyword { text, score: count as f32 / total as f32 })
+                    .collect();
+                keywords.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.text.cmp(&b.text)));
+                keywords.truncate(self.keywords_per_line);
+                keywords
+            })
+            .collect()
+    }
+}
+
+
+impl KeywordExtractor {
+    /// Starts a rayon thread for the keyword extraction model
+    ///
+    /// The model is only loaded once keywords are first requested, so runs that only use
+    /// cached websites do not pay for it. The thread stops once every handle has been dropped.
+    pub fn spawn(config: KeywordExtractionConfig) -> Self {
+        Self::spawn_inner(config, true)
+    }
+
+    /// Like `spawn`, but the model is loaded straight away instead of when keywords are first requested
+    pub fn spawn_loaded(config: KeywordExtractionConfig) -> Self {
+        Self::spawn_inner(config, false)
+    }
+
+    fn spawn_inner(config: KeywordExtractionConfig, lazy: bool) -> Self {
+        let (sender, receiver) = mpsc::channel::<(Vec<String>, SyncSender<(Vec<Vec<Keyword>>, Duration)>)>();
+        rayon::spawn(move || {
+            let first = if lazy {
+                let Ok(first) = receiver.recv() else { return };
+                Some(first)
+            } else {
+                None
+            };
+            let model = Model::load(config);
+            for (lines, sender) in first.into_iter().chain(receiver) {
+                let start = Instant::now();
+                let keywords = model.predict(&lines);
+                if sender.send((keywords, start.elapsed())).is_err() {
+                    break
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// Extracts keywords from each of the given lines
+    pub fn extract(&self, lines: Vec<String>) -> PendingKeywords<'static> {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let _ = self.sender.send((lines, sender));
+        PendingKeywords { receiver, time: None }
+    }
+
+    /// Like `extract`, but the time the model spends on these lines is added to `time`
+    pub fn extract_timed<'a>(&self, lines: Vec<String>, time: &'a Mutex<Duration>) -> PendingKeywords<'a> {
+        let PendingKeywords { receiver, .. } = self.extract(lines);
+        PendingKeywords { receiver, time: Some(time) }
+    }
+}
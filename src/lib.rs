@@ -0,0 +1,28 @@
+//! The machinery behind resume-builder: scraping job postings for keywords, and
+//! rendering resumes tailored to those keywords.
+pub mod applications;
+pub mod cache;
+pub mod career_sites;
+pub mod config;
+pub mod dedupe;
+pub mod email;
+pub mod failure;
+pub mod grouped_logs;
+pub mod import;
+pub mod job_boards;
+pub mod job_feeds;
+pub mod job_filter;
+pub mod keywords;
+pub mod page_scrapers;
+pub mod pipeline;
+pub mod progress;
+pub mod report;
+pub mod resume_gen;
+pub mod tab_pool;
+pub mod timings;
+pub mod upload;
+
+pub use keywords::KeywordExtractor;
+pub use page_scrapers::{PageData, PageScraper, ScraperState};
+pub use resume_gen::{ResumeData, ResumeTemplate, generate_resume};
+pub use timings::Timings;
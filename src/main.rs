@@ -1,203 +1,911 @@
-#![feature(once_cell_try)]
-use std::{sync::{mpsc::{self, SyncSender}, Arc}, fs::DirBuilder, path::PathBuf, hash::{Hash, Hasher}, cell::OnceCell, io::{self, Write}};
+use std::{sync::{Arc, Mutex}, path::{Path, PathBuf}, io, process::ExitCode, time::Duration};
 
 use anyhow::Context;
-use fxhash::{FxHasher, FxHashSet};
-use headless_chrome::Browser;
-use ordered_float::NotNan;
-use page_scrapers::PageData;
-use regex::Regex;
-use resume_gen::ResumeData;
-use rust_bert::pipelines::keywords_extraction::{KeywordExtractionModel, Keyword};
-use serde::Deserialize;
-use tokio::task::JoinSet;
-use tokio_rayon::rayon;
+use clap::{Parser, Subcommand, ValueEnum};
+use fxhash::{FxHashSet, FxHashMap};
+use indicatif::ProgressDrawTarget;
+use resume_builder::{
+    cache::{Cache, canonicalize_url},
+    config::{Config, JobWebsite, JobWebsiteEntry, CONFIG_PATH},
+    failure::{classification, failure_kind, render_error, tab_error, transient, FailureKind},
+    grouped_logs,
+    import,
+    keywords::KeywordExtractionConfig,
+    page_scrapers::{salary::HOURS_PER_YEAR, PageDataSerde},
+    pipeline::{
+        render::{annotate_posting, named_template},
+        scrape::{enabled_scrapers, fetch_html, is_static, rename_company, scrape_html, truncate_html},
+        cache_dir, output_dir, run, run_websites, Engine, FailedWebsites, JobSummary, Outcome, RunOptions,
+        DEFAULT_MAX_HTML_BYTES, DEFAULT_TIMEOUT_SECS, FAILED_WEBSITES_FILE_NAME, USER_AGENT
+    },
+    progress::{progress, ProgressWriter},
+    report::{keyword_trends, location_groups, salaries_csv, salary_summaries, title_word_groups, trends_csv},
+    resume_gen::{backend::RenderBackend, diff::{print_diff, ResumeContents, BASE_RESUME}, language::{Translation, DEFAULT_LANGUAGE}, lint::{lint_resume_data, DEFAULT_LANGUAGETOOL_URL}, selection::{Selection, SelectionLimits}, wizard::run_wizard, Regexes, RenderRequest},
+    tab_pool::{BrowserConfig, PooledTab, SharedBrowser, TabPool},
+    KeywordExtractor, PageData, generate_resume
+};
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
 use url::Url;
 use validator::Validate;
-use crate::{page_scrapers::{PageDataSerde, DEFAULT_SCRAPERS, ScraperState, scrape_page}, resume_gen::{use_page_data, OUTPUT_PATH, ResumeTemplate, A4_PAGE_HEIGHT_PX, SMALLEST_FONT_PERCENTAGE, Regexes}};
 
-mod page_scrapers;
-mod resume_gen;
+#[derive(Parser)]
+#[command(version, about, after_help = EXIT_CODES_HELP)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Print what would be put on each resume without rendering or writing anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Only process websites that have already been cached, skipping the rest
+    #[arg(long)]
+    offline: bool,
+    /// Ignore and replace every cached website
+    #[arg(long, conflicts_with = "offline")]
+    refresh: bool,
+    /// Ignore and replace the cache of the given website. Can be given multiple times
+    #[arg(long, value_name = "URL", conflicts_with = "offline")]
+    refresh_url: Vec<Url>,
+    /// The language of resumes that do not give their own, such as "es", instead of `resume_language`
+    #[arg(long)]
+    language: Option<String>,
+    /// Show what was chosen to be put on each resume, and toggle entries on or off before it is rendered
+    #[arg(long, conflicts_with = "dry_run")]
+    review: bool,
+    /// Run the scrapers again over the HTML saved in the cache, without fetching anything
+    #[arg(long)]
+    rescrape_cached: bool,
+    /// Only use the given scrapers, ignoring `omit_default_scrapers` and `enable_optional_scrapers`
+    #[arg(long, value_name = "SCRAPER", value_delimiter = ',')]
+    scrapers: Vec<String>,
+    /// Do not use the given scrapers, in addition to `omit_default_scrapers`
+    #[arg(long, value_name = "SCRAPER", value_delimiter = ',')]
+    omit_scrapers: Vec<String>,
+    /// Overrides `cache_dir` in config.toml
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+    /// Overrides `output_dir` in config.toml
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+    /// Report how long each phase took for every website, and in total
+    #[arg(long)]
+    timings: bool,
+    /// Log more details. Can be given twice
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Only log warnings, or only errors if given twice
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "verbose")]
+    quiet: u8,
+    /// Save the HTML, scraper errors, and a screenshot of websites that could not be scraped into
+    /// the debug folder of the output directory, to help with reporting scraper bugs
+    #[arg(long)]
+    debug_artifacts: bool,
+    /// Write what happened to every website as JSON into the given file
+    #[arg(long, value_name = "PATH")]
+    json_output: Option<PathBuf>,
+    /// How logs are written to stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat
+}
 
-#[derive(Deserialize)]
-struct Config {
-    job_requirement_websites: Vec<Url>,
-    #[serde(default)]
-    omit_default_scrapers: Vec<String>,
-    #[serde(default)]
-    enable_optional_scrapers: Vec<String>,
-    resume_data: ResumeData,
-    resume_template_path: Option<String>
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per line
+    Json
 }
 
-const CACHE_PATH: &str = ".cache/";
+#[derive(Clone, Copy, ValueEnum)]
+enum SalaryGroup {
+    /// Each word of the job title, such as "senior" or "backend"
+    TitleWord,
+    Location
+}
 
-#[tokio::main(flavor = "multi_thread")]
-async fn main() -> anyhow::Result<()> {
-    DirBuilder::new().recursive(true).create(CACHE_PATH).context("Failed to create cache directory. Do we have permissions?")?;
+#[derive(Subcommand)]
+enum Command {
+    /// Interactively fill in the resume data section of config.toml
+    Wizard,
+    /// Regenerate resumes from cached websites whenever config.toml or a template changes
+    Watch,
+    /// Keep the keyword extraction model and Chrome running, and generate resumes for jobs sent over HTTP
+    ///
+    /// Jobs are sent as `POST /job` with a JSON body like `{"url": "https://..."}`, and get the template, language
+    /// and folder name that config.toml gives by default. Jobs run in the background one at a time, unless `?wait=true`
+    /// is given to respond only once the job has finished. Only browser extensions and the websites in
+    /// `daemon_allowed_origins` can send jobs, such as with a bookmarklet like this one on those websites:
+    ///
+    /// javascript:fetch('http://127.0.0.1:7464/job',{method:'POST',headers:{'Content-Type':'application/json'},body:JSON.stringify({url:location.href})})
+    Daemon {
+        /// The port to listen on, on localhost
+        #[arg(long, default_value_t = DEFAULT_DAEMON_PORT)]
+        port: u16
+    },
+    /// Serve a small web UI for generating resumes, on localhost
+    Serve {
+        /// The port to listen on, on localhost
+        #[arg(long, default_value_t = DEFAULT_SERVE_PORT)]
+        port: u16
+    },
+    /// Generate a resume for a single website into the current directory, without needing config.toml
+    Quick {
+        url: Url,
+        /// A file containing just your resume data, or a config with a `resume_data` section
+        #[arg(long)]
+        resume: PathBuf
+    },
+    /// Scrape a single website and print what was scraped, without caching it or generating a resume
+    ///
+    /// Useful for checking what a scraper finds on a website. `--scrapers` and `--omit-scrapers` still apply
+    Scrape {
+        url: Url,
+        /// Print the scraped data as JSON, which `resume-builder render` can generate a resume from
+        #[arg(long)]
+        json: bool
+    },
+    /// Generate a resume from page data saved with `resume-builder scrape --json`, without fetching anything or
+    /// extracting keywords, such as to quickly try out changes to a template
+    ///
+    /// The resume is written where it would be for the website in the output directory
+    Render {
+        /// The JSON file of page data
+        #[arg(long)]
+        page_data: PathBuf,
+        /// A template file, "default", "sidebar", or the name of one of `templates`. Defaults to `resume_template_path`
+        #[arg(long)]
+        template: Option<String>
+    },
+    /// Try again only the websites that have failed, and have not succeeded since
+    Retry,
+    /// Add jobs tracked in another app to `job_requirement_websites` in config.toml
+    Import {
+        #[command(subcommand)]
+        source: ImportCommand
+    },
+    /// Rank the keywords of every cached website, to see which skills are most in demand
+    Report {
+        /// How many keywords to print
+        #[arg(long, default_value_t = 30)]
+        top: usize,
+        /// Also write every keyword into this CSV file
+        #[arg(long)]
+        csv: Option<PathBuf>
+    },
+    /// Summarize the pay ranges of every cached website, to see what similar jobs pay per year
+    ///
+    /// Websites cached before pay ranges were scraped need --rescrape-cached first
+    Salaries {
+        #[arg(long, value_enum, default_value_t = SalaryGroup::TitleWord)]
+        group_by: SalaryGroup,
+        /// How many groups to print
+        #[arg(long, default_value_t = 30)]
+        top: usize,
+        /// Also write every group into this CSV file
+        #[arg(long)]
+        csv: Option<PathBuf>
+    },
+    /// Show which notes and keywords differ between two generated resumes, to see what tailoring changed
+    Diff {
+        /// A resume folder, or the name of one in the output directory
+        a: String,
+        /// Another resume folder, or `base` for the resume data with nothing left out
+        #[arg(default_value = BASE_RESUME)]
+        b: String
+    },
+    /// Check the spelling and grammar of the majors and notes in `resume_data`
+    LintResume,
+    /// Manage websites that have been scraped and cached
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand
+    }
+}
 
-    let config = std::fs::read_to_string("config.toml").context("Failed to read config.toml. Does it exist? Do we have permissions?")?;
-    let config: Config = toml::from_str(&config)?;
+#[derive(Subcommand)]
+enum ImportCommand {
+    /// Import the tracked jobs exported from Simplify, as a CSV or JSON file
+    Simplify {
+        path: PathBuf
+    }
+}
 
-    config.resume_data.validate()?;
-    let resume_data = Arc::new(config.resume_data);
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// List every cached website
+    List,
+    /// Print everything that was scraped from a cached website
+    Show {
+        url: Url
+    },
+    /// Delete cached websites that are older than `cache_ttl_days`, or that cannot be read
+    Prune {
+        /// Overrides `cache_ttl_days` in config.toml
+        #[arg(long)]
+        older_than_days: Option<f64>
+    },
+    /// Delete every cached website
+    Clear,
+    /// Write every cached website into a single file, so that it can be imported on another machine
+    Export {
+        /// Where to write the export, such as backup.tar.zst
+        path: PathBuf
+    },
+    /// Add the cached websites from a file made with `cache export`
+    Import {
+        path: PathBuf
+    }
+}
 
-    let resume_template = if let Some(path) = config.resume_template_path {
-        let template = std::fs::read_to_string(path).context("Failed to read custom resume template. Does it exist? Do we have permissions?")?;
-        let font_size_regex = Regex::new(r#"font-size:(.|\n)*\d+\.*\d*.*;"#).unwrap();
-        let min_font_size = font_size_regex
-            .find_iter(&template)
-            .filter_map(|x| {
-                let line = x.as_str();
-                let number_unit_str = line.split_at(10).1.trim();
-                let number_str;
-                let multiplier;
-                if number_unit_str.ends_with("rem;") {
-                    number_str = number_unit_str.split_at(number_unit_str.len() - 4).0.trim();
-                    multiplier = 16.0;
-                } else if number_unit_str.ends_with("px;") {
-                    number_str = number_unit_str.split_at(number_unit_str.len() - 3).0.trim();
-                    multiplier = 1.0;
-                } else {
-                    return None
-                }
-                let size: Option<f64> = number_str.parse().ok();
-                size.map(|x| NotNan::new(x * multiplier).unwrap())
-            })
-            .min()
-            .map(|x| x.into())
-            .unwrap_or(16.0);
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  Success
+  1  Any other error
+  2  The config is invalid
+  3  A template could not be loaded
+  4  Chrome could not be started or connected to
+  5  A website could not be scraped
+  6  A resume could not be rendered";
+const DEFAULT_DAEMON_PORT: u16 = 7464;
+const DEFAULT_SERVE_PORT: u16 = 7465;
+const SERVE_HTML: &str = include_str!("serve.html");
 
-        if min_font_size / A4_PAGE_HEIGHT_PX < SMALLEST_FONT_PERCENTAGE {
-            return Err(anyhow::anyhow!("The smallest font size ({min_font_size}px) in the given resume template is too small to be read"))
+impl Args {
+    /// The options that are given to every run
+    fn run_options(&self) -> RunOptions {
+        RunOptions {
+            dry_run: self.dry_run,
+            offline: self.offline,
+            refresh: self.refresh,
+            refresh_url: self.refresh_url.clone(),
+            language: self.language.clone(),
+            review: self.review,
+            rescrape_cached: self.rescrape_cached,
+            scrapers: self.scrapers.clone(),
+            omit_scrapers: self.omit_scrapers.clone(),
+            cache_dir: self.cache_dir.clone(),
+            output_dir: self.output_dir.clone(),
+            timings: self.timings,
+            debug_artifacts: self.debug_artifacts,
+            json_output: self.json_output.clone()
         }
-        ResumeTemplate::Custom { template: Arc::new(template), min_font_size }
-    } else {
-        ResumeTemplate::Default
-    };
+    }
+}
 
-    let omit_default_scrapers: FxHashSet<String> = config.omit_default_scrapers.into_iter().collect();
+#[tokio::main(flavor = "multi_thread")]
+async fn main() -> ExitCode {
+    match try_main().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            let code = classification(&e).and_then(|x| x.kind).map_or(1, |kind| kind as u8);
+            ExitCode::from(code)
+        }
+    }
+}
 
-    let enabled_scrapers: FxHashSet<String> = DEFAULT_SCRAPERS
-        .into_iter()
-        .filter_map(|x| if omit_default_scrapers.contains(x) {
-                None
+async fn try_main() -> anyhow::Result<()> {
+    let mut args = Args::parse();
+    init_logging(&args);
+    let options = args.run_options();
+    match args.command.take() {
+        Some(Command::Wizard) => run_wizard(Path::new(CONFIG_PATH)),
+        Some(Command::Watch) => watch(options).await,
+        Some(Command::Daemon { port }) => daemon(options, port).await,
+        Some(Command::Serve { port }) => serve(options, port).await,
+        Some(Command::Report { top, csv }) => report(&options, top, csv.as_deref()).await,
+        Some(Command::Salaries { group_by, top, csv }) => salaries(&options, group_by, top, csv.as_deref()).await,
+        Some(Command::Diff { a, b }) => {
+            let config = Config::read()?;
+            let output_dir = output_dir(&options, config.output_dir);
+            let load = |folder: &str| if folder == BASE_RESUME {
+                Ok(ResumeContents::base(&config.resume_data))
             } else {
-                Some(x.to_string())
-            })
-        .chain(config.enable_optional_scrapers)
-        .collect();
-    let enabled_scrapers: &_ = Box::leak(Box::new(enabled_scrapers));
-
-    let (keyword_extractor_sender, keyword_receiever) = mpsc::channel::<(Vec<String>, SyncSender<Vec<Vec<Keyword>>>)>();
-    rayon::spawn(move || {
-        let keyword_extraction_model = KeywordExtractionModel::new(Default::default()).expect("Keyword Extraction Model should have initialized");
-        loop {
-            let Ok((keywords, sender)) = keyword_receiever.recv() else { break };
-            if sender.send(keyword_extraction_model.predict(&keywords).expect("Keyword Extraction Model should have worked")).is_err() {
-                break
+                ResumeContents::load(&output_dir, folder)
+            };
+            print_diff(&load(&a)?, &load(&b)?);
+            Ok(())
+        }
+        Some(Command::LintResume) => lint_resume().await,
+        Some(Command::Import { source: ImportCommand::Simplify { path } }) => {
+            let export = std::fs::read_to_string(&path).context(format!("Failed to read {path:?}. Does it exist? Do we have permissions?"))?;
+            let urls = import::simplify_export_urls(&export)?;
+            let found = urls.len();
+            let added = import::add_to_config(Path::new(CONFIG_PATH), urls)?;
+            println!("Found {found} jobs in {path:?}, and added {added} that were not already in {CONFIG_PATH}");
+            Ok(())
+        }
+        Some(Command::Quick { url, resume }) => {
+            let resume_data = (|| {
+                let resume_data = std::fs::read_to_string(&resume).context(format!("Failed to read {resume:?}. Does it exist? Do we have permissions?"))?;
+                let mut resume_data: toml::Table = toml::from_str(&resume_data)?;
+                anyhow::Ok(match resume_data.remove("resume_data") {
+                    Some(resume_data) => resume_data.try_into()?,
+                    None => toml::Value::Table(resume_data).try_into()?
+                })
+            })().map_err(failure_kind(FailureKind::Config))?;
+            let config = Config {
+                job_requirement_websites: vec![JobWebsiteEntry::Detailed(JobWebsite { url, template: None, output_name: Some(".".into()), language: None })],
+                resume_data,
+                output_dir: Some(".".into()),
+                base_resume: Some(false),
+                ..Default::default()
+            };
+            let engine = Engine::new(&config);
+            run(&options, config, engine).await
+        }
+        Some(Command::Scrape { url, json }) => scrape(&options, url, json).await,
+        Some(Command::Render { page_data, template }) => render(&options, &page_data, template).await,
+        Some(Command::Retry) => {
+            let mut config = Config::read()?;
+            let path = cache_dir(&options, config.cache_dir.clone()).join(FAILED_WEBSITES_FILE_NAME);
+            let failed = match std::fs::read_to_string(&path) {
+                Ok(failed) => toml::from_str::<FailedWebsites>(&failed)?.websites,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+                Err(e) => return Err(anyhow::Error::new(e).context(format!("Failed to read {path:?}. Do we have permissions?")))
+            };
+            if failed.is_empty() {
+                tracing::info!("No websites have failed since they last succeeded");
+                return Ok(())
             }
+            config.job_requirement_websites = failed.into_iter().map(JobWebsiteEntry::Detailed).collect();
+            let engine = Engine::new(&config);
+            run(&options, config, engine).await
         }
-    });
-    
-    let browser = OnceCell::new();
+        Some(Command::Cache { command }) => cache_command(&options, command).await,
+        None => {
+            let config = Config::read()?;
+            let engine = Engine::new(&config);
+            run(&options, config, engine).await
+        }
+    }
+}
 
-    macro_rules! browser {
-        () => {
-            browser.get_or_try_init(|| Browser::default().context("Failed to start Headless Chrome. Do you have Chrome installed?"))?
-        };
+fn init_logging(args: &Args) {
+    let level = match args.verbose as i16 - args.quiet as i16 {
+        ..=-2 => Level::ERROR,
+        -1 => Level::WARN,
+        0 => Level::INFO,
+        1 => Level::DEBUG,
+        _ => Level::TRACE
+    };
+    if args.quiet > 0 || matches!(args.log_format, LogFormat::Json) {
+        progress().set_draw_target(ProgressDrawTarget::hidden());
     }
+    let subscriber = tracing_subscriber::registry().with(LevelFilter::from_level(level));
+    match args.log_format {
+        // Websites are processed concurrently, so the lines of each one are printed together once it is finished
+        LogFormat::Text => subscriber
+            .with(grouped_logs::UrlLayer)
+            .with(tracing_subscriber::fmt::layer().without_time().with_target(false).with_writer(|| ProgressWriter))
+            .init(),
+        // Every line already has the URL of its website
+        LogFormat::Json => subscriber.with(tracing_subscriber::fmt::layer().json().with_writer(|| ProgressWriter)).init()
+    }
+}
 
-    let mut scrape_tasks = JoinSet::<anyhow::Result<_>>::new();
-    DirBuilder::new().recursive(true).create(OUTPUT_PATH).context("Failed to create resumes directory. Do we have permissions?")?;
-    let regexes = Arc::new(Regexes::default());
-    
-    for url in config.job_requirement_websites {
-        let url = Arc::new(url);
-        let mut hasher = FxHasher::default();
-        url.hash(&mut hasher);
-        let hash = hasher.finish();
-        let cached_file_path = PathBuf::from(CACHE_PATH).join(hash.to_string());
-
-        if cached_file_path.try_exists().context(format!("Failed to check if a website has been cached. Do we have read permissions for {CACHE_PATH}?"))? {
-            let tab = browser!().new_tab()?;
-            let resume_data = resume_data.clone();
-            let resume_template = resume_template.clone();
-            let regexes = regexes.clone();
-
-            scrape_tasks.spawn(async move {
-                let bytes = tokio::fs::read(&cached_file_path).await.context(format!("Failed to read {cached_file_path:?}"))?;
-                let page_data: Option<PageDataSerde> = bitcode::decode(&bytes).context(format!("Failed to deserialize {cached_file_path:?}. Consider deleting it."))?;
-                let Some(page_data) = page_data else { return Ok(()) };
-                let page_data = PageData::from(page_data);
-                use_page_data(page_data, tab, resume_data, resume_template, regexes).await.context(format!("Failed to process {cached_file_path:?}"))
-            });
-            continue;
-        }
-
-        if url.scheme() == "http" {
-            eprintln!("Warning!, you are attempting to scrape {} without https. Consider modifying the URL to use https instead.", url);
-        }
-
-        let tab = browser!().new_tab()?;
-        let keyword_extractor_sender = keyword_extractor_sender.clone();
-        let resume_data = resume_data.clone();
-        let resume_template = resume_template.clone();
-        let regexes = regexes.clone();
-
-        scrape_tasks.spawn(async move {
-            let url2 = url.clone();
-            let (html, tab) = tokio_rayon::spawn(move || {
-                tab.navigate_to(url2.as_str())?
-                    .wait_until_navigated()?
-                    .get_content()
-                    .map(|x| (x, tab))
-            }).await?;
-
-            let state = ScraperState {
-                html,
-                url: url.clone(),
-                keyword_extractor_sender,
-                enabled_scrapers
+fn days(duration: Duration) -> f64 {
+    duration.as_secs_f64() / (24.0 * 60.0 * 60.0)
+}
+
+async fn cache_command(options: &RunOptions, command: CacheCommand) -> anyhow::Result<()> {
+    // The cache can be managed without a valid config
+    let config = Config::read().ok();
+    let cache_ttl_days = config.as_ref().and_then(|x| x.cache_ttl_days);
+    let cache_backend = config.as_ref().map(|x| x.cache_backend).unwrap_or_default();
+    let cache = Cache::new(cache_dir(options, config.and_then(|x| x.cache_dir)), cache_backend)?;
+
+    match command {
+        CacheCommand::List => {
+            for (key, entry) in cache.entries().await? {
+                match entry {
+                    Ok(entry) => {
+                        let (company, job_title) = match entry.page_data.map(PageData::from) {
+                            Some(page_data) => (page_data.company, page_data.job_title),
+                            None => ("-".into(), "No Page Data!".into())
+                        };
+                        println!("{:>6.1} days  {company}  {job_title}  {}", days(entry.age()), entry.url);
+                    }
+                    Err(_) => println!("Unreadable entry {key:?}")
+                }
+            }
+        }
+        CacheCommand::Show { url } => {
+            let Some(entry) = cache.get(&canonicalize_url(&url)).await? else {
+                return Err(anyhow::anyhow!("{url} has not been cached"))
             };
-            
-            let ((page_data, errors), state) = tokio_rayon::spawn(move || {
-                (scrape_page(&state), state)
-            }).await;
-            let page_data_is_none = page_data.is_none();
-
-            tokio::spawn(async move {
-                let stdout = io::stdout();
-                let mut stdout = stdout.lock();
-                writeln!(stdout, "Finished scraping {}", state.url).unwrap();
-
-                let stderr = io::stderr();
-                let mut stderr = stderr.lock();
-
-                for error in errors {
-                    writeln!(stderr, "Error for {}: {error}", state.url).unwrap();
+            println!("Scraped {:.1} days ago", days(entry.age()));
+            match entry.page_data {
+                Some(page_data) => println!("{:#?}", PageData::from(page_data)),
+                None => println!("No Page Data!")
+            }
+        }
+        CacheCommand::Prune { older_than_days } => {
+            let ttl_days = older_than_days.or(cache_ttl_days);
+            let mut pruned = 0usize;
+            for (key, entry) in cache.entries().await? {
+                let expired = match entry {
+                    Ok(entry) => ttl_days.is_some_and(|ttl_days| days(entry.age()) >= ttl_days),
+                    Err(_) => true
+                };
+                if expired {
+                    cache.remove_key(&key).await?;
+                    pruned += 1;
                 }
+            }
+            println!("Pruned {pruned} cached websites");
+        }
+        CacheCommand::Clear => {
+            let entries = cache.entries().await?;
+            for (key, _) in &entries {
+                cache.remove_key(key).await?;
+            }
+            println!("Deleted {} cached websites", entries.len());
+        }
+        CacheCommand::Export { path } => {
+            let exported = cache.export(&path).await?;
+            println!("Exported {exported} cached websites to {path:?}");
+        }
+        CacheCommand::Import { path } => {
+            let imported = cache.import(&path).await?;
+            println!("Imported {imported} cached websites from {path:?}");
+        }
+    }
+    Ok(())
+}
+
+/// Every website in the cache that something could be scraped from
+async fn cached_postings(options: &RunOptions, config: &Config) -> anyhow::Result<Vec<PageData>> {
+    let cache = Cache::new(cache_dir(options, config.cache_dir.clone()), config.cache_backend)?;
+    Ok(cache
+        .entries()
+        .await?
+        .into_iter()
+        .filter_map(|(_, entry)| entry.ok()?.page_data.map(PageData::from))
+        .collect())
+}
 
-                if page_data_is_none {
-                    writeln!(stderr, "No Page Data!").unwrap();
+/// Prints the lowest, median and highest pay of the cached websites in each group
+async fn salaries(options: &RunOptions, group_by: SalaryGroup, top: usize, csv: Option<&Path>) -> anyhow::Result<()> {
+    let config = Config::read()?;
+    let postings = cached_postings(options, &config).await?;
+    let groups = match group_by {
+        SalaryGroup::TitleWord => title_word_groups,
+        SalaryGroup::Location => location_groups
+    };
+    let summaries = salary_summaries(&postings, groups);
+
+    let group_width = summaries.iter().take(top).map(|x| x.group.len()).max().unwrap_or_default().max(5);
+    println!("{:group_width$}  {:>8}  {:>8}  {:>8}  {:>8}", "Group", "Postings", "Min", "Median", "Max");
+    for summary in summaries.iter().take(top) {
+        let thousands = |x: f64| format!("{:.0}k", x / 1000.0);
+        println!("{:group_width$}  {:>8}  {:>8}  {:>8}  {:>8}", summary.group, summary.postings, thousands(summary.min), thousands(summary.median), thousands(summary.max));
+    }
+    let with_salary = postings.iter().filter(|x| x.salary.is_some()).count();
+    println!("\n{with_salary} of {} cached postings have a pay range. Hourly pay is counted as {HOURS_PER_YEAR} hours a year", postings.len());
+
+    if let Some(path) = csv {
+        std::fs::write(path, salaries_csv(&summaries)).context(format!("Failed to write {path:?}. Do we have permissions?"))?;
+    }
+    Ok(())
+}
+
+/// Prints the most demanded keywords across every cached website, and whether the resume data has them
+async fn report(options: &RunOptions, top: usize, csv: Option<&Path>) -> anyhow::Result<()> {
+    let config = Config::read()?;
+    let postings = cached_postings(options, &config).await?;
+    let trends = keyword_trends(&postings, &config.resume_data);
+
+    let keyword_width = trends.iter().take(top).map(|x| x.keyword.len()).max().unwrap_or_default().max(7);
+    println!("{:>4}  {:keyword_width$}  {:>8}  {:>7}  In resume  Sections", "Rank", "Keyword", "Postings", "Score");
+    for (i, trend) in trends.iter().take(top).enumerate() {
+        let sections = trend.sections.iter().map(|x| x.name()).collect::<Vec<_>>().join(", ");
+        println!("{:>4}  {:keyword_width$}  {:>8}  {:>7.2}  {:9}  {sections}", i + 1, trend.keyword, trend.postings, trend.total_score, if trend.covered { "yes" } else { "no" });
+    }
+    let shown = trends.len().min(top);
+    let covered = trends.iter().take(top).filter(|x| x.covered).count();
+    println!("\nYour resume covers {covered} of the top {shown} keywords, across {} cached postings", postings.len());
+
+    if let Some(path) = csv {
+        std::fs::write(path, trends_csv(&trends)).context(format!("Failed to write {path:?}. Do we have permissions?"))?;
+    }
+    Ok(())
+}
+
+/// Prints the spelling and grammar mistakes in the resume data, along with where they are in config.toml
+async fn lint_resume() -> anyhow::Result<()> {
+    let config = Config::read()?;
+    let http_client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    let languagetool_url = match config.languagetool_url {
+        Some(url) => url,
+        None => Url::parse(DEFAULT_LANGUAGETOOL_URL)?
+    };
+    let language = config.resume_language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+    let issues = lint_resume_data(&http_client, &languagetool_url, &config.resume_data, language).await?;
+
+    for issue in &issues {
+        println!("{}: {} {:?}", issue.field, issue.message, issue.text);
+        if !issue.replacements.is_empty() {
+            println!("    Suggestions: {}", issue.replacements.iter().take(3).cloned().collect::<Vec<_>>().join(", "));
+        }
+    }
+    if issues.is_empty() {
+        println!("No mistakes found");
+    } else {
+        println!("\nFound {} possible mistakes", issues.len());
+    }
+    Ok(())
+}
+
+/// Runs `run` in offline mode, then again every time one of the source files is saved
+async fn watch(mut options: RunOptions) -> anyhow::Result<()> {
+    options.offline = true;
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| { let _ = sender.send(event); })?;
+    let mut watched_dirs = FxHashSet::<PathBuf>::default();
+    // Kept between runs, so that the model and Chrome are only started again when their settings change
+    let mut engine: Option<(Engine, BrowserConfig, KeywordExtractionConfig)> = None;
+
+    loop {
+        let source_paths = match Config::read() {
+            Ok(config) => {
+                let source_paths = config.source_paths();
+                let engine = match &engine {
+                    Some((engine, browser, keyword_extraction)) if *browser == config.browser && *keyword_extraction == config.keyword_extraction => engine.clone(),
+                    _ => {
+                        let new_engine = Engine::new(&config);
+                        engine = Some((new_engine.clone(), config.browser.clone(), config.keyword_extraction.clone()));
+                        new_engine
+                    }
+                };
+                if let Err(e) = run(&options, config, engine).await {
+                    tracing::error!("{e:?}");
                 }
-            });
+                source_paths
+            }
+            Err(e) => {
+                tracing::error!("{e:?}");
+                vec![PathBuf::from(CONFIG_PATH)]
+            }
+        };
+        let source_paths: FxHashSet<PathBuf> = source_paths
+            .into_iter()
+            .filter_map(|x| x.canonicalize().ok())
+            .collect();
 
-            let page_data = page_data.map(PageDataSerde::from);
-            let encoded = bitcode::encode(&page_data).unwrap();
-            tokio::spawn(async move { tokio::fs::write(&cached_file_path, encoded).await.expect(&format!("{cached_file_path:?} should be writable")) });
-            let Some(page_data) = page_data else {
-                return Ok(())
-            };
-            let page_data = PageData::from(page_data);
-            use_page_data(page_data, tab, resume_data, resume_template, regexes).await.context(format!("Failed to process {url}"))
+        // Editors often save by replacing the file, so the parent directories are watched instead of the files
+        let dirs: FxHashSet<PathBuf> = source_paths.iter().filter_map(|x| x.parent().map(Path::to_path_buf)).collect();
+        for dir in watched_dirs.difference(&dirs) {
+            let _ = watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&watched_dirs) {
+            watcher.watch(dir, notify::RecursiveMode::NonRecursive).context(format!("Failed to watch {dir:?}"))?;
+        }
+        watched_dirs = dirs;
+        tracing::info!("Watching for changes...");
+
+        loop {
+            let Some(event) = receiver.recv().await else { return Ok(()) };
+            let event: notify::Event = event?;
+            if event.kind.is_access() || !event.paths.iter().any(|x| source_paths.contains(x)) {
+                continue;
+            }
+            // Saving a file can emit several events, so wait for them all to arrive before regenerating
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            while receiver.try_recv().is_ok() { }
+            break
+        }
+    }
+}
+
+/// Starts the keyword extraction model and Chrome, then runs every job sent to `POST /job` with them
+///
+/// config.toml is read again for every job, so changes to it are picked up without restarting.
+async fn daemon(options: RunOptions, port: u16) -> anyhow::Result<()> {
+    let config = Config::read()?;
+    let engine = Engine::started(&config)?;
+
+    // Lets bookmarklets and browser extensions send jobs from the page being viewed. Only localhost can
+    // connect, and jobs can only give a URL
+    let allowed_origins = config.daemon_allowed_origins;
+    let cors = tower_http::cors::CorsLayer::new()
+        .allow_origin(tower_http::cors::AllowOrigin::predicate(move |origin, _| {
+            let origin = origin.to_str().unwrap_or_default();
+            origin.starts_with("chrome-extension://") ||
+                origin.starts_with("moz-extension://") ||
+                allowed_origins.iter().any(|x| x.trim_end_matches('/') == origin)
+        }))
+        .allow_methods([axum::http::Method::POST])
+        .allow_headers([axum::http::header::CONTENT_TYPE])
+        .allow_private_network(true);
+    let app = axum::Router::new()
+        .route("/job", axum::routing::post(daemon_job))
+        .layer(cors)
+        .with_state(Arc::new(DaemonState { options, engine, run_lock: tokio::sync::Mutex::new(()) }));
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await.context(format!("Failed to listen on port {port}. Is another daemon already running?"))?;
+    tracing::info!("Listening for jobs on http://127.0.0.1:{port}/job");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+struct DaemonState {
+    options: RunOptions,
+    engine: Engine,
+    /// Held while a job runs, so that jobs do not write the same files at the same time
+    run_lock: tokio::sync::Mutex<()>
+}
+
+/// The body of `POST /job`
+///
+/// Only the URL can be given, as any allowed website can send jobs, and the rest of a `JobWebsite` can read
+/// and write files anywhere.
+#[derive(Deserialize)]
+struct DaemonJobRequest {
+    url: Url
+}
+
+#[derive(Deserialize)]
+struct DaemonJobQuery {
+    /// Respond once the job has finished, instead of straight away
+    #[serde(default)]
+    wait: bool
+}
+
+async fn run_daemon_job(state: &DaemonState, url: Url) -> anyhow::Result<()> {
+    let _lock = state.run_lock.lock().await;
+    let mut config = Config::read()?;
+    config.job_requirement_websites = vec![JobWebsiteEntry::Url(url)];
+    let result = run(&state.options, config, state.engine.clone()).await;
+    if let Err(e) = &result {
+        tracing::error!("{e:?}");
+    }
+    result
+}
+
+async fn daemon_job(
+    axum::extract::State(state): axum::extract::State<Arc<DaemonState>>,
+    axum::extract::Query(query): axum::extract::Query<DaemonJobQuery>,
+    axum::Json(request): axum::Json<DaemonJobRequest>
+) -> (axum::http::StatusCode, String) {
+    if !query.wait {
+        tokio::spawn(async move {
+            let _ = run_daemon_job(&state, request.url).await;
         });
+        return (axum::http::StatusCode::ACCEPTED, "Queued\n".into())
+    }
+
+    match run_daemon_job(&state, request.url).await {
+        Ok(()) => (axum::http::StatusCode::OK, "Done\n".into()),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}\n"))
+    }
+}
+
+/// A job submitted through the web UI of `serve`
+#[derive(Serialize, Clone)]
+struct ServeJob {
+    url: Url,
+    /// `"running"`, or the status of the finished website
+    status: &'static str,
+    job: Option<JobSummary>,
+    ats_warnings: Vec<String>,
+    error: Option<String>,
+    #[serde(skip)]
+    resume_path: Option<PathBuf>
+}
+
+struct ServeState {
+    options: RunOptions,
+    engine: Engine,
+    /// Every job submitted since starting, indexed by their ID
+    jobs: Mutex<Vec<ServeJob>>,
+    /// Held while a job runs, so that jobs do not write the same files at the same time
+    run_lock: tokio::sync::Mutex<()>
+}
+
+#[derive(Deserialize)]
+struct ServeJobRequest {
+    url: Url
+}
+
+/// Serves a web UI for generating resumes from a job URL, backed by the same engine as `daemon`
+///
+/// - `GET /` is the web UI
+/// - `POST /jobs` starts generating a resume for `{"url": "https://..."}`, and responds with `{"id": ...}`
+/// - `GET /jobs/<id>` is the status of a job, as a `ServeJob`
+/// - `GET /jobs/<id>/resume.pdf` is the resume of a finished job
+async fn serve(options: RunOptions, port: u16) -> anyhow::Result<()> {
+    let engine = Engine::started(&Config::read()?)?;
+    let state = Arc::new(ServeState { options, engine, jobs: Mutex::new(vec![]), run_lock: tokio::sync::Mutex::new(()) });
+
+    let app = axum::Router::new()
+        .route("/", axum::routing::get(|| async { axum::response::Html(SERVE_HTML) }))
+        .route("/jobs", axum::routing::post(serve_submit))
+        .route("/jobs/:id", axum::routing::get(serve_status))
+        .route("/jobs/:id/resume.pdf", axum::routing::get(serve_resume))
+        .with_state(state);
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await.context(format!("Failed to listen on port {port}. Is resume-builder already being served?"))?;
+    tracing::info!("Serving the web UI on http://127.0.0.1:{port}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn serve_submit(
+    axum::extract::State(state): axum::extract::State<Arc<ServeState>>,
+    axum::Json(request): axum::Json<ServeJobRequest>
+) -> Result<axum::Json<serde_json::Value>, (axum::http::StatusCode, String)> {
+    let mut config = Config::read().map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    let url = canonicalize_url(&request.url);
+    config.job_requirement_websites = vec![JobWebsiteEntry::Url(url.clone())];
+
+    let id = {
+        let mut jobs = state.jobs.lock().unwrap();
+        jobs.push(ServeJob { url, status: "running", job: None, ats_warnings: vec![], error: None, resume_path: None });
+        jobs.len() - 1
+    };
+
+    tokio::spawn(async move {
+        let result = {
+            let _lock = state.run_lock.lock().await;
+            run_websites(&state.options, config, state.engine.clone()).await
+        };
+        let mut jobs = state.jobs.lock().unwrap();
+        let job = &mut jobs[id];
+        match result.map(|results| results.into_iter().next()) {
+            Ok(Some(result)) => {
+                job.status = result.outcome.status();
+                job.job = result.job;
+                match result.outcome {
+                    Outcome::Rendered(resume) => {
+                        job.resume_path = Some(resume.path);
+                        job.ats_warnings = resume.ats_warnings;
+                    }
+                    Outcome::Failed(e) => job.error = Some(format!("{e:#}")),
+                    Outcome::NoPageData => job.error = Some("Nothing could be scraped from this website. Is there a scraper for it?".into()),
+                    Outcome::Incomplete(missing) => job.error = Some(format!("The {} of this website could not be scraped", missing.join(", "))),
+                    Outcome::Filtered(reason) => job.error = Some(reason),
+                    Outcome::DryRun | Outcome::NotCached | Outcome::Duplicate(_) => {}
+                }
+            }
+            Ok(None) => job.status = "failed",
+            Err(e) => {
+                job.status = "failed";
+                job.error = Some(format!("{e:#}"));
+            }
+        }
+    });
+
+    Ok(axum::Json(serde_json::json!({ "id": id })))
+}
+
+async fn serve_status(
+    axum::extract::State(state): axum::extract::State<Arc<ServeState>>,
+    axum::extract::Path(id): axum::extract::Path<usize>
+) -> Result<axum::Json<ServeJob>, axum::http::StatusCode> {
+    state.jobs.lock().unwrap().get(id).cloned().map(axum::Json).ok_or(axum::http::StatusCode::NOT_FOUND)
+}
+
+async fn serve_resume(
+    axum::extract::State(state): axum::extract::State<Arc<ServeState>>,
+    axum::extract::Path(id): axum::extract::Path<usize>
+) -> Result<([(axum::http::header::HeaderName, &'static str); 1], Vec<u8>), axum::http::StatusCode> {
+    let path = state.jobs
+        .lock()
+        .unwrap()
+        .get(id)
+        .and_then(|job| job.resume_path.clone())
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+    let resume = tokio::fs::read(&path).await.map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+    Ok(([(axum::http::header::CONTENT_TYPE, "application/pdf")], resume))
+}
+
+/// Scrapes a single website without caching it or generating a resume, and prints what was scraped
+///
+/// config.toml is used for the scrapers, Chrome and keyword extraction if there is one.
+async fn scrape(options: &RunOptions, url: Url, json: bool) -> anyhow::Result<()> {
+    let config = Config::read().ok();
+    let (omit_default_scrapers, enable_optional_scrapers, paragraph_scrapers) = config
+        .as_ref()
+        .map(|x| (x.omit_default_scrapers.clone(), x.enable_optional_scrapers.clone(), x.paragraph_scrapers.clone()))
+        .unwrap_or_default();
+    let enabled_scrapers = enabled_scrapers(options, omit_default_scrapers, enable_optional_scrapers);
+    let static_hosts = config.as_ref().map(|x| x.static_hosts.clone()).unwrap_or_default();
+    let navigation_timeout = Duration::from_secs_f64(config.as_ref().and_then(|x| x.navigation_timeout_secs).unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let max_html_bytes = config.as_ref().and_then(|x| x.max_html_bytes).unwrap_or(DEFAULT_MAX_HTML_BYTES);
+    let browser = config.as_ref().map(|x| x.browser.clone()).unwrap_or_default();
+    browser.validate().map_err(failure_kind(FailureKind::Config))?;
+    let keyword_extractor = KeywordExtractor::spawn(config.map(|x| x.keyword_extraction).unwrap_or_default());
+
+    let url = Arc::new(canonicalize_url(&url));
+    let tab_pool = Arc::new(TabPool::new(1, Arc::new(SharedBrowser::new(browser))));
+    let http_client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    let is_static = is_static(&url, &enabled_scrapers, &static_hosts);
+    let (html, tab) = fetch_html(&url, is_static, &http_client, &tab_pool, navigation_timeout, &enabled_scrapers).await?;
+    drop(tab);
+    let html = truncate_html(html, max_html_bytes, &url);
+    let (page_data, errors, _, timings) = scrape_html(html, url.clone(), keyword_extractor, enabled_scrapers.clone(), Arc::new(paragraph_scrapers)).await;
+    let Some(page_data) = page_data else {
+        return Err(failure_kind(FailureKind::Scrape)(anyhow::anyhow!("None of the scrapers {enabled_scrapers:?} could scrape {url}")))
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&PageDataSerde::from(page_data))?);
+        return Ok(())
+    }
+    println!("{} at {}", page_data.job_title, page_data.company);
+    println!("{url}");
+    if let Some(location) = &page_data.location {
+        println!("Location: {location}");
+    }
+    if let Some(salary) = &page_data.salary {
+        println!("Salary: {salary}");
+    }
+    println!("Scraped by {} in {:.1?}, of which {:.1?} was keyword extraction", page_data.scrapers.join(", "), timings.scraping, timings.keyword_extraction);
+    if !errors.is_empty() {
+        println!("{} scrapers failed", errors.len());
     }
 
-    while let Some(result) = scrape_tasks.join_next().await {
-        result??;
+    let mut keywords: Vec<_> = page_data.keywords.into_iter().collect();
+    keywords.sort_by(|a, b| b.data.score.total_cmp(&a.data.score));
+    println!("\n{} keywords:", keywords.len());
+    for keyword in keywords {
+        let sources: Vec<_> = keyword.data.sources
+            .iter()
+            .map(|x| format!("{} in {}", x.scraper, x.section.name()))
+            .collect();
+        println!("{:>8.3}  {}  ({})", keyword.data.score, keyword.key, sources.join(", "));
     }
+    Ok(())
+}
 
-    println!("Resumes completed successfully!");
+/// Generates a resume from the page data in the given JSON file, with the given template or `resume_template_path`
+async fn render(options: &RunOptions, page_data_path: &Path, template: Option<String>) -> anyhow::Result<()> {
+    let config = Config::read()?;
+    config.resume_data.validate().context("resume_data is invalid").map_err(failure_kind(FailureKind::Config))?;
+    config.browser.validate().map_err(failure_kind(FailureKind::Config))?;
+    let page_data = std::fs::read_to_string(page_data_path)
+        .context(format!("Failed to read {page_data_path:?}. Does it exist? Do we have permissions?"))
+        .and_then(|x| serde_json::from_str::<PageDataSerde>(&x).context(format!("{page_data_path:?} is not page data from `resume-builder scrape --json`")))
+        .map_err(failure_kind(FailureKind::Config))?;
+    let mut page_data = PageData::from(page_data);
+    rename_company(&mut page_data, &config.company_names);
+
+    let template = template.or_else(|| config.resume_template_path.clone()).unwrap_or_else(|| "default".into());
+    let regexes = Arc::new(Regexes::default());
+    let resume_template = named_template(template, &config.templates, &config.section_regions, &regexes, &mut FxHashMap::default()).map_err(failure_kind(FailureKind::Template))?;
+    let language = options.language.clone().or_else(|| config.resume_language.clone()).unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let translation = Translation::get(&language).map_err(failure_kind(FailureKind::Config))?;
+    let selection_limits = SelectionLimits {
+        max_experience_entries: config.max_experience_entries,
+        max_bullets_per_entry: config.max_bullets_per_entry,
+        education_order: config.education_order,
+        drop_education_entries: config.drop_education_entries
+    };
+    let resume_data = Arc::new(config.resume_data);
+    let selection = Selection::new(&resume_data, &page_data, selection_limits, config.selection_strategy);
+    let annotated_selection = config.annotate_postings.then(|| selection.clone());
+    let paths = config.output_layout.paths(&output_dir(options, config.output_dir), &page_data, None);
+    let render_timeout = Duration::from_secs_f64(config.render_timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+
+    let tab_pool = Arc::new(TabPool::new(1, Arc::new(SharedBrowser::new(config.browser))));
+    let tab = match config.render_backend {
+        RenderBackend::Chrome => Some(tab_pool.acquire().map_err(tab_error)?),
+        _ => None
+    };
+    let renderer = config.render_backend.renderer(tab.as_ref().map(PooledTab::tab), config.render_program.map(Into::into));
+    let request = RenderRequest { selection, translation, resume_template, paper: config.paper, paths: &paths, keep_old_versions: config.keep_old_resumes.unwrap_or(true) };
+    let render = generate_resume(page_data.clone(), renderer, resume_data.clone(), regexes, request);
+    let resume = match tokio::time::timeout(render_timeout, render).await {
+        Ok(resume) => resume.map_err(render_error)?,
+        Err(_) => return Err(transient(anyhow::anyhow!("Rendering took longer than {render_timeout:?}")))
+    };
+    for warning in &resume.ats_warnings {
+        tracing::warn!("{warning}");
+    }
+    if let Some(selection) = annotated_selection {
+        annotate_posting(&paths, &page_data, &resume_data, &selection, &resume).await;
+    }
+    if !resume.up_to_date {
+        println!("Rendered {:?} in {:.1?}", resume.path, resume.timings.rendering + resume.timings.pdf_write);
+    }
     Ok(())
 }
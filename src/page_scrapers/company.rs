@@ -0,0 +1,48 @@
+//! Readable company names, as URLs often only have a slug like "acme_inc"
+use scraper::{Html, Selector};
+
+
+/// The name of the organization hiring for the job, from the JSON-LD `JobPosting` on the page
+fn json_ld_company(html: &Html) -> Option<String> {
+    fn find(value: &serde_json::Value) -> Option<String> {
+        match value {
+            serde_json::Value::Array(values) => values.iter().find_map(find),
+            serde_json::Value::Object(object) => object
+                .get("hiringOrganization")
+                .and_then(|x| match x {
+                    serde_json::Value::String(name) => Some(name.clone()),
+                    x => x["name"].as_str().map(ToString::to_string)
+                })
+                .or_else(|| object.get("@graph").and_then(find)),
+            _ => None
+        }
+    }
+
+    html.select(&Selector::parse(r#"script[type="application/ld+json"]"#).unwrap())
+        .filter_map(|x| serde_json::from_str(&x.text().collect::<String>()).ok())
+        .find_map(|x| find(&x))
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+
+/// Turns a slug like "acme_inc" into "Acme Inc", leaving words that already have capitals as they are
+pub fn clean_slug(slug: &str) -> String {
+    slug.split(['_', '-', '+', ' '])
+        .filter(|x| !x.is_empty())
+        .map(|word| {
+            if word.chars().any(char::is_uppercase) {
+                return word.to_string()
+            }
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+
+/// The company's name as given by the page itself, or the cleaned up slug from its URL otherwise
+pub fn company_name(html: &Html, slug: &str) -> String {
+    json_ld_company(html).unwrap_or_else(|| clean_slug(slug))
+}
@@ -0,0 +1,42 @@
+//! Decoding pages that are not served as UTF-8, which would otherwise scrape into mojibake
+use encoding_rs::{Encoding, UTF_8};
+use regex::bytes::Regex;
+
+/// How far into a page a `<meta charset>` is looked for, as in the HTML spec
+const META_SCAN_BYTES: usize = 1024;
+
+
+/// The encoding named by the `charset` parameter of a `Content-Type` header
+fn header_encoding(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .filter_map(|x| x.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, label)| Encoding::for_label(label.trim().trim_matches('"').as_bytes()))
+}
+
+
+/// The encoding named by a `<meta charset>` or `<meta http-equiv="Content-Type">` near the start of the page
+fn meta_encoding(html: &[u8]) -> Option<&'static Encoding> {
+    let meta_regex = Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([a-z0-9_\-:.]+)"#).unwrap();
+    let start = &html[..html.len().min(META_SCAN_BYTES)];
+    meta_regex
+        .captures(start)
+        .and_then(|c| Encoding::for_label(&c[1]))
+}
+
+
+/// Decodes a page fetched over HTTP, using its byte order mark, then the charset in its `Content-Type`,
+/// then its `<meta charset>`, and UTF-8 if none of those are given
+pub fn decode_html(html: &[u8], content_type: Option<&str>) -> String {
+    let encoding = Encoding::for_bom(html)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(header_encoding))
+        .or_else(|| meta_encoding(html))
+        .unwrap_or(UTF_8);
+    if encoding != UTF_8 {
+        tracing::debug!("Decoding the page from {}", encoding.name());
+    }
+    // Also strips the byte order mark
+    encoding.decode(html).0.into_owned()
+}
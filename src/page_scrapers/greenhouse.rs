@@ -0,0 +1,50 @@
+use scraper::Selector;
+use url::Url;
+
+use super::{company::company_name, description_text, salary::find_salary, sections::section_lines, PageScraper, PageData, ScraperState};
+
+/// A scraper for job boards hosted by Greenhouse
+#[derive(Default)]
+pub struct GreenhouseScraper;
+
+impl GreenhouseScraper {
+    fn is_applicable(url: &Url) -> bool {
+        url.host_str().is_some_and(|x| x.ends_with("greenhouse.io"))
+    }
+}
+
+impl PageScraper for GreenhouseScraper {
+    const NAME: &'static str = "greenhouse";
+
+    fn scrape(state: &ScraperState) -> Option<anyhow::Result<PageData>> {
+        if !Self::is_applicable(&state.url) {
+            return None;
+        }
+        let mut page_data = state.create_page_data();
+        let scraper = state.get_scraper();
+        // Boards are at boards.greenhouse.io/<company>/jobs/<id>
+        page_data.company = company_name(&scraper, state.url.path_segments()?.next()?);
+
+        page_data.job_title = scraper
+            .select(&Selector::parse("h1.app-title, h1.section-header, div.job__title h1").unwrap())
+            .next()
+            .map(|x| x.text().collect::<String>().trim().to_string())?;
+        page_data.location = scraper
+            .select(&Selector::parse("div.location, div.job__location").unwrap())
+            .next()
+            .map(|x| x.text().collect::<String>().trim().to_string());
+
+        let description = scraper
+            .select(&Selector::parse("div#content, div.job__description").unwrap())
+            .next()?;
+        page_data.salary = find_salary(&description.text().collect::<String>());
+        page_data.description = description_text(description);
+        state.insert_keywords(&mut page_data, Self::NAME, section_lines(description, state.extracts_paragraphs(Self::NAME)));
+
+        Some(Ok(page_data))
+    }
+
+    fn is_static(url: &Url) -> bool {
+        Self::is_applicable(url)
+    }
+}
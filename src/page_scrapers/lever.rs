@@ -0,0 +1,59 @@
+use scraper::Selector;
+use url::Url;
+
+use super::{company::company_name, description_text, salary::find_salary, sections::section_lines, PageScraper, PageData, ScraperState};
+
+/// A scraper for job boards hosted by Lever
+#[derive(Default)]
+pub struct LeverScraper;
+
+impl LeverScraper {
+    fn is_applicable(url: &Url) -> bool {
+        url.host_str().is_some_and(|x| x == "jobs.lever.co")
+    }
+}
+
+impl PageScraper for LeverScraper {
+    const NAME: &'static str = "lever";
+
+    fn scrape(state: &ScraperState) -> Option<anyhow::Result<PageData>> {
+        if !Self::is_applicable(&state.url) {
+            return None;
+        }
+        let mut page_data = state.create_page_data();
+        let scraper = state.get_scraper();
+        // Postings are at jobs.lever.co/<company>/<id>
+        page_data.company = company_name(&scraper, state.url.path_segments()?.next()?);
+
+        page_data.job_title = scraper
+            .select(&Selector::parse("div.posting-headline h2").unwrap())
+            .next()
+            .map(|x| x.text().collect::<String>().trim().to_string())?;
+        page_data.location = scraper
+            .select(&Selector::parse("div.posting-categories .location").unwrap())
+            .next()
+            .map(|x| x.text().collect::<String>().trim().trim_end_matches('/').trim().to_string());
+
+        let (mut sections, mut lines, mut description) = (vec![], vec![], vec![]);
+        for wrapper in scraper.select(&Selector::parse("div.section-wrapper.page-full-width").unwrap()) {
+            let (wrapper_sections, wrapper_lines) = section_lines(wrapper, state.extracts_paragraphs(Self::NAME));
+            sections.extend(wrapper_sections);
+            lines.extend(wrapper_lines);
+            description.push(description_text(wrapper));
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        page_data.salary = scraper
+            .select(&Selector::parse("div.section-wrapper.page-full-width").unwrap())
+            .find_map(|x| find_salary(&x.text().collect::<String>()));
+        page_data.description = description.join("\n");
+        state.insert_keywords(&mut page_data, Self::NAME, (sections, lines));
+
+        Some(Ok(page_data))
+    }
+
+    fn is_static(url: &Url) -> bool {
+        Self::is_applicable(url)
+    }
+}
@@ -1,18 +1,36 @@
-use std::{ops::Add, sync::{mpsc::{self, SyncSender}, Arc}, hash::Hash};
+use std::{ops::Add, sync::{Arc, Mutex}, hash::Hash, time::{Duration, SystemTime, UNIX_EPOCH}};
 
 use fxhash::FxHashSet;
-use rust_bert::pipelines::keywords_extraction::Keyword;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::page_scrapers::workday::WorkdayScraper;
+use crate::{page_scrapers::workday::WorkdayScraper, keywords::{Keyword, KeywordExtractor, PendingKeywords}};
 
-use self::simplify::SimplifyScraper;
+use self::{greenhouse::GreenhouseScraper, lever::LeverScraper, sanitize::{sanitize_lines, sanitize_text}, sections::KeywordSection, simplify::SimplifyScraper};
 
-mod simplify;
-mod workday;
+pub mod company;
+pub mod encoding;
+pub mod greenhouse;
+pub mod lever;
+pub mod salary;
+pub mod sanitize;
+pub mod sections;
+pub mod simplify;
+pub mod workday;
 
 
-pub(super) const DEFAULT_SCRAPERS: [&str; 2] = [SimplifyScraper::NAME, WorkdayScraper::NAME];
+/// Elements whose text is never shown, or is not part of the content of the page
+const HIDDEN_ELEMENTS: [&str; 10] = ["head", "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer"];
+/// Elements whose text is on its own lines
+const BLOCK_ELEMENTS: [&str; 24] = [
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "h1", "h2", "h3", "h4", "h5", "h6",
+    "hr", "li", "main", "ol", "p", "pre", "section", "tr", "ul"
+];
+/// How much each part of `PageData` counts towards `PageData::completeness`, which add up to 1
+const COMPLETENESS_WEIGHTS: [(&str, f32); 4] = [("job title", 0.3), ("company", 0.3), ("keywords", 0.3), ("description", 0.1)];
+
+
+pub const DEFAULT_SCRAPERS: [&str; 4] = [SimplifyScraper::NAME, WorkdayScraper::NAME, GreenhouseScraper::NAME, LeverScraper::NAME];
 
 
 macro_rules! scrape_page {
@@ -22,7 +40,7 @@ macro_rules! scrape_page {
                 scrape_page!($state, $scraper)
             },
             || {
-                scrape_page!($state, $($scrapers)*)
+                scrape_page!($state, $($scrapers),+)
             }
         );
         if let Some(data1_inner) = data1 {
@@ -47,7 +65,11 @@ macro_rules! scrape_page {
             match <$scraper>::scrape($state) {
                 None => (None, vec![]),
                 Some(Err(e)) => (None, vec![e]),
-                Some(Ok(x)) => (Some(x), vec![])
+                Some(Ok(mut x)) => {
+                    x.sanitize();
+                    x.scrapers.push(<$scraper>::NAME.to_string());
+                    (Some(x), vec![])
+                }
             }
         } else {
             (None, vec![])
@@ -57,10 +79,40 @@ macro_rules! scrape_page {
 }
 
 
-#[derive(Debug, bitcode::Encode, bitcode::Decode, Clone)]
-pub(super) struct KeyWithData<K: Hash + Eq, V> {
-    pub(super) key: K,
-    pub(super) data: V
+#[derive(Debug, bitcode::Encode, bitcode::Decode, Serialize, Deserialize, Clone)]
+pub struct KeyWithData<K: Hash + Eq, V> {
+    pub key: K,
+    pub data: V
+}
+
+
+/// Where a keyword was found in a job posting
+#[derive(Debug, bitcode::Encode, bitcode::Decode, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KeywordSource {
+    /// The name of the scraper that found the keyword
+    pub scraper: String,
+    pub section: KeywordSection
+}
+
+
+#[derive(Debug, bitcode::Encode, bitcode::Decode, Serialize, Deserialize, Clone)]
+pub struct KeywordData {
+    /// The sum of the scores of this keyword in every line it was found in
+    pub score: f32,
+    /// Every scraper and section this keyword was found in, without duplicates
+    pub sources: Vec<KeywordSource>
+}
+
+
+impl KeywordData {
+    fn merge(&mut self, other: KeywordData) {
+        self.score += other.score;
+        for source in other.sources {
+            if !self.sources.contains(&source) {
+                self.sources.push(source);
+            }
+        }
+    }
 }
 
 
@@ -79,32 +131,161 @@ impl<K: Hash + Eq, V> PartialEq for KeyWithData<K, V> {
 }
 
 
-pub(super) fn scrape_page(state: &ScraperState) -> (Option<PageData>, Vec<anyhow::Error>) {
-    scrape_page!(state, SimplifyScraper, WorkdayScraper)
+/// The text inside the given element, with each piece of text on its own line and whitespace cleaned up
+pub fn description_text(element: scraper::ElementRef) -> String {
+    element.text()
+        .map(|x| x.replace('\u{a0}', " ").split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+
+/// Appends the text inside the given element that would be visible, with a line break around every block element
+fn push_visible_text(element: scraper::ElementRef, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            scraper::Node::Text(x) => text.push_str(x),
+            scraper::Node::Element(x) => {
+                if HIDDEN_ELEMENTS.contains(&x.name()) || x.attr("hidden").is_some() || x.attr("aria-hidden") == Some("true") {
+                    continue
+                }
+                let block = BLOCK_ELEMENTS.contains(&x.name());
+                if block {
+                    text.push('\n');
+                }
+                push_visible_text(scraper::ElementRef::wrap(child).unwrap(), text);
+                if block {
+                    text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+
+pub fn scrape_page(state: &ScraperState) -> (Option<PageData>, Vec<anyhow::Error>) {
+    scrape_page!(state, SimplifyScraper, WorkdayScraper, GreenhouseScraper, LeverScraper)
+}
+
+
+/// Whether any of the enabled scrapers says that the given website can be fetched without Chrome
+pub fn is_static_page(url: &Url, enabled_scrapers: &FxHashSet<String>) -> bool {
+    (enabled_scrapers.contains(SimplifyScraper::NAME) && SimplifyScraper::is_static(url)) ||
+    (enabled_scrapers.contains(WorkdayScraper::NAME) && WorkdayScraper::is_static(url)) ||
+    (enabled_scrapers.contains(GreenhouseScraper::NAME) && GreenhouseScraper::is_static(url)) ||
+    (enabled_scrapers.contains(LeverScraper::NAME) && LeverScraper::is_static(url))
+}
+
+
+/// The element that the first enabled scraper that needs one says to wait for before the given website is scraped
+pub fn wait_for_selector(url: &Url, enabled_scrapers: &FxHashSet<String>) -> Option<&'static str> {
+    [
+        (SimplifyScraper::NAME, SimplifyScraper::wait_for(url)),
+        (WorkdayScraper::NAME, WorkdayScraper::wait_for(url)),
+        (GreenhouseScraper::NAME, GreenhouseScraper::wait_for(url)),
+        (LeverScraper::NAME, LeverScraper::wait_for(url))
+    ]
+        .into_iter()
+        .find_map(|(name, selector)| selector.filter(|_| enabled_scrapers.contains(name)))
 }
 
 
 /// Useful information gathered from a website that can be used to generate a resume
 #[derive(Debug, Clone)]
-pub(crate) struct PageData {
+pub struct PageData {
     /// Keywords regarding the job that can be used to generate a resume tailored for the job
     /// 
     /// Keywords must be a noun, verb, or adjective. Prepositions, pronouns, etc, are not useful.
-    pub(crate) keywords: FxHashSet<KeyWithData<String, f32>>,
-    pub(crate) url: Arc<Url>,
-    pub(crate) job_title: String,
-    pub(crate) company: String
+    pub keywords: FxHashSet<KeyWithData<String, KeywordData>>,
+    pub url: Arc<Url>,
+    pub job_title: String,
+    pub company: String,
+    pub location: Option<String>,
+    /// The pay range as written in the job posting
+    pub salary: Option<String>,
+    /// The text of the job description, one paragraph or list item per line, so that it can be used again
+    /// without scraping the website again
+    pub description: String,
+    /// When the website was scraped, in seconds since the unix epoch
+    pub scraped_at: u64,
+    /// The names of the scrapers that this data came from
+    pub scrapers: Vec<String>
+}
+
+
+impl PageData {
+    /// Cleans up the text that came straight from the website with `sanitize_text`
+    ///
+    /// Keywords are left as they are, as they were extracted from text that was already clean.
+    fn sanitize(&mut self) {
+        self.job_title = sanitize_text(&self.job_title);
+        self.company = sanitize_text(&self.company);
+        self.location = self.location.as_deref().map(sanitize_text).filter(|x| !x.is_empty());
+        self.salary = self.salary.as_deref().map(sanitize_text).filter(|x| !x.is_empty());
+        self.description = sanitize_lines(&self.description);
+    }
+
+    /// How much of what a tailored resume needs was scraped, from 0 to 1, along with the names of what is missing
+    pub fn completeness(&self) -> (f32, Vec<&'static str>) {
+        let mut completeness = 0.0;
+        let mut missing = vec![];
+        for (name, weight) in COMPLETENESS_WEIGHTS {
+            let present = match name {
+                "job title" => !self.job_title.trim().is_empty(),
+                "company" => !self.company.trim().is_empty(),
+                "keywords" => !self.keywords.is_empty(),
+                _ => !self.description.trim().is_empty()
+            };
+            if present {
+                completeness += weight;
+            } else {
+                missing.push(name);
+            }
+        }
+        (completeness, missing)
+    }
+
+    /// Adds the scores of keywords that are already in this data, or inserts them otherwise
+    ///
+    /// Each line of keywords was found in the section at the same index of `sections`, by the given scraper.
+    pub fn insert_keywords(&mut self, scraper: &str, sections: &[KeywordSection], keywords: Vec<Vec<Keyword>>) {
+        for (&section, line) in sections.iter().zip(keywords) {
+            for x in line {
+                let sources = vec![KeywordSource { scraper: scraper.to_string(), section }];
+                self.insert_keyword(KeyWithData { key: x.text, data: KeywordData { score: x.score, sources } });
+            }
+        }
+    }
+
+    fn insert_keyword(&mut self, k: KeyWithData<String, KeywordData>) {
+        if let Some(mut old_k) = self.keywords.take(&k) {
+            old_k.data.merge(k.data);
+            self.keywords.insert(old_k);
+        } else {
+            self.keywords.insert(k);
+        }
+    }
 }
 
 
 impl From<PageDataSerde> for PageData {
     fn from(value: PageDataSerde) -> Self {
-        Self {
+        let mut page_data = Self {
             keywords: value.keywords,
             url: Arc::new(value.url.parse().expect("Serialized URL should have been valid")),
             job_title: value.job_title,
-            company: value.company
-        }
+            company: value.company,
+            location: value.location,
+            salary: value.salary,
+            description: value.description,
+            scraped_at: value.scraped_at,
+            scrapers: value.scrapers
+        };
+        // It may have been cached before scraped text was sanitized, or edited by hand for `resume-builder render`
+        page_data.sanitize();
+        page_data
     }
 }
 
@@ -115,22 +296,39 @@ impl From<PageData> for PageDataSerde {
             keywords: value.keywords,
             url: value.url.to_string(),
             job_title: value.job_title,
-            company: value.company
+            company: value.company,
+            location: value.location,
+            salary: value.salary,
+            description: value.description,
+            scraped_at: value.scraped_at,
+            scrapers: value.scrapers
         }
     }
 }
 
 
 /// Useful information gathered from a website that can be used to generate a resume
-#[derive(Debug, bitcode::Encode, bitcode::Decode, Clone)]
-pub(super) struct PageDataSerde {
+#[derive(Debug, bitcode::Encode, bitcode::Decode, Serialize, Deserialize, Clone)]
+pub struct PageDataSerde {
     /// Keywords regarding the job that can be used to generate a resume tailored for the job
     /// 
     /// Keywords must be a noun, verb, or adjective. Prepositions, pronouns, etc, are not useful.
-    keywords: FxHashSet<KeyWithData<String, f32>>,
+    keywords: FxHashSet<KeyWithData<String, KeywordData>>,
+    #[serde(deserialize_with = "deserialize_url")]
     url: String,
     job_title: String,
-    company: String
+    company: String,
+    location: Option<String>,
+    salary: Option<String>,
+    description: String,
+    scraped_at: u64,
+    scrapers: Vec<String>
+}
+
+
+/// Only deserializes valid URLs, as they are parsed again when converted into `PageData`
+fn deserialize_url<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Url::deserialize(deserializer).map(String::from)
 }
 
 
@@ -138,55 +336,82 @@ impl Add for PageData {
     type Output = Self;
 
     fn add(mut self, rhs: Self) -> Self::Output {
+        self.scrapers.extend(rhs.scrapers);
+        self.location = self.location.or(rhs.location);
+        self.salary = self.salary.or(rhs.salary);
+        if self.description.is_empty() {
+            self.description = rhs.description;
+        }
         for other_k in rhs.keywords {
-            if let Some(mut self_k) = self.keywords.take(&other_k) {
-                self_k.data += other_k.data;
-                self.keywords.insert(self_k);
-            } else {
-                self.keywords.insert(other_k);
-            }
+            self.insert_keyword(other_k);
         }
         self
     }
 }
 
 
-pub(super) struct ScraperState {
-    pub(super) html: String,
-    pub(super) url: Arc<Url>,
-    pub(super) keyword_extractor_sender: mpsc::Sender<(Vec<String>, SyncSender<Vec<Vec<Keyword>>>)>,
-    pub(super) enabled_scrapers: &'static FxHashSet<String>
+pub struct ScraperState {
+    pub html: String,
+    pub url: Arc<Url>,
+    pub keyword_extractor: KeywordExtractor,
+    pub enabled_scrapers: Arc<FxHashSet<String>>,
+    /// The scrapers that also extract keywords from every sentence of paragraphs
+    pub paragraph_scrapers: Arc<FxHashSet<String>>,
+    /// The total time spent extracting keywords for the scrapers
+    pub keyword_extraction_time: Mutex<Duration>
 }
 
 
-pub(super) struct PendingKeywords(mpsc::Receiver<Vec<Vec<Keyword>>>);
-
+impl ScraperState {
+    pub fn get_scraper(&self) -> scraper::Html {
+        scraper::Html::parse_document(&self.html)
+    }
 
-impl PendingKeywords {
-    pub(super) fn get(self) -> Vec<Vec<Keyword>> {
-        self.0.recv().unwrap()
+    /// The text of the page that would be visible, one paragraph or list item per line, leaving out navigation,
+    /// headers and footers
+    ///
+    /// Useful for scrapers of websites that have no reliable selectors for their description.
+    pub fn visible_text(&self) -> String {
+        let mut text = String::new();
+        push_visible_text(self.get_scraper().root_element(), &mut text);
+        sanitize_lines(&text)
     }
-}
 
+    /// Whether the scraper with the given name should also extract keywords from paragraphs
+    pub fn extracts_paragraphs(&self, scraper: &str) -> bool {
+        self.paragraph_scrapers.contains(scraper)
+    }
 
-impl ScraperState {
-    pub(super) fn get_scraper(&self) -> scraper::Html {
-        scraper::Html::parse_document(&self.html)
+    pub fn extract_keywords(&self, keywords: Vec<String>) -> PendingKeywords<'_> {
+        self.keyword_extractor.extract_timed(keywords, &self.keyword_extraction_time)
     }
 
-    pub(super) fn extract_keywords(&self, keywords: Vec<String>) -> PendingKeywords {
-        let (sender, receiver) = mpsc::sync_channel(1);
-        let _ = self.keyword_extractor_sender.send((keywords, sender));
-        PendingKeywords(receiver)
+    /// Extracts keywords from the given lines, as from `sections::section_lines`, and from the job title,
+    /// then inserts them into the given page data
+    pub fn insert_keywords(&self, page_data: &mut PageData, scraper: &str, (mut sections, mut lines): (Vec<KeywordSection>, Vec<String>)) {
+        sections.push(KeywordSection::Title);
+        lines.push(page_data.job_title.clone());
+        let keywords = self.extract_keywords(lines).get();
+        page_data.insert_keywords(scraper, &sections, keywords);
     }
 
-    pub(super) fn create_page_data(&self) -> PageData {
-        PageData { keywords: Default::default(), url: self.url.clone(), job_title: String::new(), company: String::new() }
+    pub fn create_page_data(&self) -> PageData {
+        PageData {
+            keywords: Default::default(),
+            url: self.url.clone(),
+            job_title: String::new(),
+            company: String::new(),
+            location: None,
+            salary: None,
+            description: String::new(),
+            scraped_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            scrapers: vec![]
+        }
     }
 }
 
 
-pub(super) trait PageScraper {
+pub trait PageScraper {
     const NAME: &'static str;
 
     /// Scrapes the given html, which is retrieved from the given URL
@@ -199,4 +424,20 @@ pub(super) trait PageScraper {
     /// was not designed for if it will be able to produce no misleading keywords. Examples of misleading keywords
     /// are those that are collected from any section that is not pertaining to the job, such as a navbar or footer (exceptions do exist of course).
     fn scrape(state: &ScraperState) -> Option<anyhow::Result<PageData>>;
+
+    /// Whether the given website serves all of the HTML this scraper needs without running any JavaScript
+    /// 
+    /// Such websites are fetched with a plain HTTP request instead of through Chrome, which is much faster.
+    /// Return false for websites this scraper is not applicable to.
+    fn is_static(_url: &Url) -> bool {
+        false
+    }
+
+    /// A CSS selector for an element that is loaded after the rest of the given website, which Chrome
+    /// waits for before the website is scraped
+    ///
+    /// It is scraped anyway if the element never appears. Return None for websites this scraper is not applicable to.
+    fn wait_for(_url: &Url) -> Option<&'static str> {
+        None
+    }
 }
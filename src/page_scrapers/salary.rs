@@ -0,0 +1,66 @@
+//! Pay ranges written in job postings, such as "$120,000 - $150,000" or "$45/hr"
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Hours in a year of full time work, so that hourly pay can be compared with yearly pay
+pub const HOURS_PER_YEAR: f64 = 2080.0;
+/// Amounts below this much a year are not pay, such as a gift card or a price
+const MIN_YEARLY_PAY: f64 = 10_000.0;
+
+
+fn salary_regex() -> &'static Regex {
+    static SALARY_REGEX: OnceLock<Regex> = OnceLock::new();
+    SALARY_REGEX.get_or_init(|| Regex::new(
+        r"(?i)[$€£]\s?\d[\d,]*(\.\d+)?\s?k?(\s?(-|–|—|to)\s?[$€£]?\s?\d[\d,]*(\.\d+)?\s?k?)?(\s?(/|per|an|a)\s?(hour|hr|year|yr|annum))?"
+    ).unwrap())
+}
+
+
+fn amount_regex() -> &'static Regex {
+    static AMOUNT_REGEX: OnceLock<Regex> = OnceLock::new();
+    AMOUNT_REGEX.get_or_init(|| Regex::new(r"(?i)(\d[\d,]*(?:\.\d+)?)\s?(k)?").unwrap())
+}
+
+
+/// A pay range, in pay per year
+#[derive(Debug, Clone, Copy)]
+pub struct SalaryRange {
+    pub min: f64,
+    pub max: f64
+}
+
+
+impl SalaryRange {
+    /// Reads a pay range written like in a job posting, converting hourly pay into yearly pay
+    pub fn parse(text: &str) -> Option<Self> {
+        let amounts: Vec<f64> = amount_regex()
+            .captures_iter(text)
+            .filter_map(|c| {
+                let amount: f64 = c[1].replace(',', "").parse().ok()?;
+                Some(if c.get(2).is_some() { amount * 1000.0 } else { amount })
+            })
+            .collect();
+        let lowercase = text.to_lowercase();
+        let multiplier = if lowercase.contains("hour") || lowercase.contains("hr") { HOURS_PER_YEAR } else { 1.0 };
+        let min = *amounts.first()? * multiplier;
+        let max = amounts.get(1).map_or(min, |x| x * multiplier);
+        if min < MIN_YEARLY_PAY {
+            return None
+        }
+        Some(Self { min: min.min(max), max: min.max(max) })
+    }
+
+    pub fn midpoint(&self) -> f64 {
+        (self.min + self.max) / 2.0
+    }
+}
+
+
+/// Finds the first pay range in the given text of a job posting, as it is written
+pub fn find_salary(text: &str) -> Option<String> {
+    salary_regex()
+        .find_iter(text)
+        .map(|x| x.as_str().trim().to_string())
+        .find(|x| SalaryRange::parse(x).is_some())
+}
@@ -0,0 +1,36 @@
+//! Cleans up text scraped from websites, as it ends up in folder names, reports, metadata and notifications
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+
+/// Characters that take up no space, which some websites put inside titles and company names
+const ZERO_WIDTH: [char; 4] = ['\u{200b}', '\u{200c}', '\u{200d}', '\u{feff}'];
+
+
+/// Removes markup and control characters from the given scraped text, and collapses its whitespace
+///
+/// Some websites put tags like `<b>` into the JSON that titles and company names are read from.
+pub fn sanitize_text(text: &str) -> String {
+    static TAG_REGEX: OnceLock<Regex> = OnceLock::new();
+    let tag_regex = TAG_REGEX.get_or_init(|| Regex::new(r"</?[a-zA-Z][^<>]*>").unwrap());
+    tag_regex
+        .replace_all(text, " ")
+        .chars()
+        .filter(|c| !ZERO_WIDTH.contains(c))
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+
+/// Like `sanitize_text`, but keeps each line on its own, leaving out lines that end up empty
+pub fn sanitize_lines(text: &str) -> String {
+    text.lines()
+        .map(sanitize_text)
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
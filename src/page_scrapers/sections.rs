@@ -0,0 +1,118 @@
+//! Which part of a job posting a line of text is in, such as the requirements or the responsibilities
+use scraper::{ElementRef, Node, Selector};
+use serde::{Deserialize, Serialize};
+
+
+/// Words in a heading that start a list of requirements, such as "Basic Qualifications" or "What you'll bring"
+const REQUIREMENTS_HEADINGS: [&str; 11] = [
+    "requirement", "qualification", "skill", "you have", "bring", "need", "looking for", "must have",
+    "nice to have", "preferred", "about you"
+];
+/// Words in a heading that start a list of responsibilities, such as "What you'll do" or "Duties"
+const RESPONSIBILITIES_HEADINGS: [&str; 7] = ["responsibilit", "you will", "you'll", "you do", "duties", "day to day", "this role"];
+/// Bold text longer than this is part of a paragraph, not a heading
+const MAX_HEADING_LENGTH: usize = 60;
+
+
+/// The part of a job posting that a keyword was found in
+#[derive(Debug, bitcode::Encode, bitcode::Decode, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordSection {
+    Title,
+    Requirements,
+    Responsibilities,
+    /// Anywhere else in the description, such as a list of benefits or a list without a heading
+    Description
+}
+
+
+impl KeywordSection {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Requirements => "requirements",
+            Self::Responsibilities => "responsibilities",
+            Self::Description => "description"
+        }
+    }
+
+    fn from_heading(heading: &str) -> Self {
+        let heading = heading.to_lowercase().replace('’', "'");
+        if REQUIREMENTS_HEADINGS.iter().any(|x| heading.contains(x)) {
+            Self::Requirements
+        } else if RESPONSIBILITIES_HEADINGS.iter().any(|x| heading.contains(x)) {
+            Self::Responsibilities
+        } else {
+            Self::Description
+        }
+    }
+}
+
+
+/// Whether the given element looks like the heading of a list
+///
+/// Many job boards use bold text instead of real headings. Bold text inside a list item is not a heading.
+fn is_heading(element: ElementRef) -> bool {
+    match element.value().name() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => true,
+        "strong" | "b" => {
+            element.text().collect::<String>().trim().len() <= MAX_HEADING_LENGTH &&
+                !element.ancestors().any(|x| x.value().as_element().is_some_and(|x| x.name() == "li"))
+        }
+        _ => false
+    }
+}
+
+
+/// Whether all of the text of the given element is bold, as in `<p><strong>Requirements</strong></p>`
+fn is_bold_only(element: ElementRef) -> bool {
+    let text = element.text().collect::<String>();
+    let bold_text = element.select(&Selector::parse("strong, b").unwrap()).flat_map(|x| x.text()).collect::<String>();
+    !text.trim().is_empty() && text.trim() == bold_text.trim()
+}
+
+
+/// Splits the given paragraph into sentences, so that keywords are extracted from each sentence on its own
+fn sentences(paragraph: &str) -> Vec<String> {
+    let mut sentences = vec![];
+    let mut start = 0;
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at_end = chars.peek().map_or(true, |(_, next)| next.is_whitespace());
+        if matches!(c, '.' | '!' | '?') && at_end {
+            sentences.push(paragraph[start..i + 1].trim().to_string());
+            start = i + 1;
+        }
+    }
+    sentences.push(paragraph[start..].trim().to_string());
+    sentences.retain(|x| !x.is_empty());
+    sentences
+}
+
+
+/// Every list item in the given description, along with the section of the heading before it
+///
+/// If `paragraphs` is true, every sentence of paragraphs outside of lists is also included.
+pub fn section_lines(description: ElementRef, paragraphs: bool) -> (Vec<KeywordSection>, Vec<String>) {
+    let mut section = KeywordSection::Description;
+    let mut sections = vec![];
+    let mut lines = vec![];
+    for node in description.descendants() {
+        let Node::Element(_) = node.value() else { continue };
+        let element = ElementRef::wrap(node).unwrap();
+        let in_list = element.ancestors().any(|x| x.value().as_element().is_some_and(|x| x.name() == "li"));
+        if is_heading(element) {
+            section = KeywordSection::from_heading(&element.text().collect::<String>());
+        } else if element.value().name() == "li" {
+            sections.push(section);
+            lines.push(element.text().map(|x| x.replace("\u{a0}", " ")).collect());
+        } else if paragraphs && element.value().name() == "p" && !in_list && !is_bold_only(element) {
+            let paragraph: String = element.text().map(|x| x.replace("\u{a0}", " ")).collect();
+            for sentence in sentences(&paragraph) {
+                sections.push(section);
+                lines.push(sentence);
+            }
+        }
+    }
+    (sections, lines)
+}
@@ -2,7 +2,7 @@ use super::{PageScraper, PageData, ScraperState};
 
 /// A scraper for Simplify job sites
 #[derive(Default)]
-pub(super) struct SimplifyScraper;
+pub struct SimplifyScraper;
 
 impl PageScraper for SimplifyScraper {
     const NAME: &'static str = "simplify";
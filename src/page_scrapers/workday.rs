@@ -1,52 +1,73 @@
 use scraper::Selector;
+use url::Url;
 
-use super::{PageScraper, PageData, ScraperState};
+use super::{company::company_name, description_text, salary::find_salary, sections::section_lines, PageScraper, PageData, ScraperState};
 
 /// A scraper for MyWorkday job sites
 #[derive(Default)]
-pub(super) struct WorkdayScraper;
+pub struct WorkdayScraper;
+
+/// Hosts of Workday job sites, which can be on any data center such as `wd1`, `wd3` or `wd5`
+const WORKDAY_HOSTS: [&str; 2] = ["myworkdaysite.com", "myworkdayjobs.com"];
+/// The description of the job, which Workday loads after the rest of the page
+const DESCRIPTION_SELECTOR: &str = "div[data-automation-id=\"jobPostingDescription\"]";
+
+impl WorkdayScraper {
+    fn is_applicable(url: &Url) -> bool {
+        url.host_str().is_some_and(|host| WORKDAY_HOSTS.iter().any(|x| host == *x || host.ends_with(&format!(".{x}"))))
+    }
+
+    /// The company's part of the given URL, which is in the path of myworkdaysite.com, such as
+    /// `wd5.myworkdaysite.com/en-US/recruiting/<company>/<site>/job/...`, and the subdomain of
+    /// myworkdayjobs.com, such as `<company>.wd1.myworkdayjobs.com/en-US/<site>/job/...`
+    fn company_slug(url: &Url) -> Option<&str> {
+        let host = url.host_str()?;
+        if host.ends_with("myworkdayjobs.com") {
+            return host.split('.').next()
+        }
+        // Locales look like `en-US`
+        let is_locale = |x: &str| x.len() == 5 && x.as_bytes()[2] == b'-' && x.chars().filter(|c| *c != '-').all(|c| c.is_ascii_alphabetic());
+        let mut segments = url.path_segments()?.skip_while(|x| is_locale(x));
+        match segments.next()? {
+            "recruiting" => segments.next(),
+            x => Some(x)
+        }
+    }
+}
 
 impl PageScraper for WorkdayScraper {
     const NAME: &'static str = "workday";
 
     fn scrape(state: &ScraperState) -> Option<anyhow::Result<PageData>> {
-        if !state.url.host_str().unwrap().contains("myworkdaysite.com") {
+        if !Self::is_applicable(&state.url) {
             return None;
         }
         let mut page_data = state.create_page_data();
-        page_data.company = state.url.path_segments().expect("Job application website should have been valid").take(2).last()?.to_string();
         let scraper = state.get_scraper();
+        page_data.company = company_name(&scraper, Self::company_slug(&state.url)?);
 
         page_data.job_title = scraper
             .select(&Selector::parse("h2[data-automation-id=\"jobPostingHeader\"]").unwrap())
             .next()
             .map(|x| x.text().map(|x| x.replace("\u{a0}", " ")).collect())?;
 
+        page_data.location = scraper
+            .select(&Selector::parse("div[data-automation-id=\"locations\"] dd").unwrap())
+            .next()
+            .map(|x| x.text().map(|x| x.replace("\u{a0}", " ")).collect::<String>().trim().to_string());
+
         let job_posting_desc = scraper
-            .select(&Selector::parse("div[data-automation-id=\"jobPostingDescription\"]").unwrap())
+            .select(&Selector::parse(DESCRIPTION_SELECTOR).unwrap())
             .next()?;
-        
-        let lines = job_posting_desc
-            .select(&Selector::parse("li").unwrap())
-            .map(|x| x.text().map(|x| x.replace("\u{a0}", " ")).collect())
-            .collect();
-
-        state
-            .extract_keywords(lines)
-            .get()
-            .into_iter()
-            .map(|x| x.into_iter())
-            .flatten()
-            .for_each(|x| {
-                let k = super::KeyWithData { key: x.text, data: x.score };
-                if let Some(mut old_k) = page_data.keywords.take(&k) {
-                    old_k.data += k.data;
-                    page_data.keywords.insert(old_k);
-                } else {
-                    page_data.keywords.insert(k);
-                }
-            });
+        page_data.salary = find_salary(&job_posting_desc.text().collect::<String>());
+        page_data.description = description_text(job_posting_desc);
+
+        state.insert_keywords(&mut page_data, Self::NAME, section_lines(job_posting_desc, state.extracts_paragraphs(Self::NAME)));
         
         Some(Ok(page_data))
     }
+
+    fn wait_for(url: &Url) -> Option<&'static str> {
+        Self::is_applicable(url).then_some(DESCRIPTION_SELECTOR)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+//! Finding jobs to add to a run on company boards, careers sites and feeds
+use anyhow::Context;
+use fxhash::FxHashSet;
+use regex::Regex;
+use url::Url;
+
+use crate::{
+    cache::canonicalize_url,
+    career_sites::CareerSite,
+    config::JobWebsiteEntry,
+    failure::{failure_kind, FailureKind},
+    job_boards::CompanyBoard,
+    job_feeds::JobFeed,
+    job_filter::JobFilter
+};
+
+
+/// Lists the jobs on every board whose titles match `title_regex`, and are wanted by `job_filter`
+///
+/// Boards that cannot be listed are skipped with a warning, as are all boards when offline.
+pub(super) async fn company_board_websites(boards: &[CompanyBoard], title_regex: Option<&str>, job_filter: &JobFilter, http_client: &reqwest::Client, offline: bool) -> anyhow::Result<Vec<JobWebsiteEntry>> {
+    if boards.is_empty() {
+        return Ok(vec![])
+    }
+    if offline {
+        tracing::warn!("Skipping company_boards as this run is offline");
+        return Ok(vec![])
+    }
+    let title_regex = title_regex
+        .map(Regex::new)
+        .transpose()
+        .context("company_board_title_regex is not a valid regex")
+        .map_err(failure_kind(FailureKind::Config))?;
+
+    let mut websites = vec![];
+    for board in boards {
+        match board.postings(http_client).await {
+            Ok(postings) => {
+                let count = websites.len();
+                websites.extend(
+                    postings
+                        .into_iter()
+                        .filter(|posting| title_regex.as_ref().map_or(true, |x| x.is_match(&posting.title)) && job_filter.wants_title(&posting.title))
+                        .map(|posting| JobWebsiteEntry::Url(posting.url))
+                );
+                tracing::info!("Found {} matching jobs on {board}", websites.len() - count);
+            }
+            Err(e) => tracing::warn!("Skipping {board}: {e:?}")
+        }
+    }
+    Ok(websites)
+}
+
+
+/// Lists the postings on every careers site whose titles are wanted by `job_filter`
+///
+/// Sites that cannot be read are skipped with a warning, as are all sites when offline.
+pub(super) async fn career_site_websites(sites: &[CareerSite], job_filter: &JobFilter, http_client: &reqwest::Client, offline: bool) -> Vec<JobWebsiteEntry> {
+    if sites.is_empty() {
+        return vec![]
+    }
+    if offline {
+        tracing::warn!("Skipping career_sites as this run is offline");
+        return vec![]
+    }
+
+    let mut websites = vec![];
+    for site in sites {
+        match site.postings(http_client).await {
+            Ok(postings) => {
+                let count = websites.len();
+                websites.extend(
+                    postings
+                        .into_iter()
+                        .filter(|posting| job_filter.wants_title(&posting.title))
+                        .map(|posting| JobWebsiteEntry::Url(posting.url))
+                );
+                tracing::info!("Found {} matching jobs on {}", websites.len() - count, site.url);
+            }
+            Err(e) => tracing::warn!("Skipping {}: {e:?}", site.url)
+        }
+    }
+    websites
+}
+
+
+/// Lists the items in every feed that are wanted, and are not in `seen`
+///
+/// Feeds that cannot be read are skipped with a warning, as are all feeds when offline.
+pub(super) async fn new_feed_items(feeds: &[JobFeed], seen: &[Url], job_filter: &JobFilter, http_client: &reqwest::Client, offline: bool) -> Vec<Url> {
+    if feeds.is_empty() {
+        return vec![]
+    }
+    if offline {
+        tracing::warn!("Skipping job_feeds as this run is offline");
+        return vec![]
+    }
+    let seen: FxHashSet<Url> = seen.iter().map(canonicalize_url).collect();
+
+    let mut items = vec![];
+    for feed in feeds {
+        match feed.postings(http_client).await {
+            Ok(postings) => {
+                let count = items.len();
+                for posting in postings.into_iter().filter(|x| job_filter.wants_title(&x.title)) {
+                    let url = canonicalize_url(&posting.url);
+                    if !seen.contains(&url) && !items.contains(&url) {
+                        items.push(url);
+                    }
+                }
+                tracing::info!("Found {} new jobs in {}", items.len() - count, feed.url);
+            }
+            Err(e) => tracing::warn!("Skipping {}: {e:?}", feed.url)
+        }
+    }
+    items
+}
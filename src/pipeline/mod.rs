@@ -0,0 +1,744 @@
+//! Scraping every website in the config and rendering their resumes
+use std::{fs::DirBuilder, io, path::{Path, PathBuf}, sync::{Arc, OnceLock}, time::{Duration, Instant}};
+
+use anyhow::Context;
+use fxhash::{FxHashMap, FxHashSet};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use tokio::{task::JoinSet, sync::Semaphore};
+use tracing::{Instrument, info_span};
+use url::Url;
+use validator::Validate;
+
+use crate::{
+    applications::{self, Application, APPLICATIONS_FILE_NAME},
+    cache::{Cache, CacheEntry, canonicalize_url},
+    config::{Config, JobWebsite, JobWebsiteEntry},
+    email::EmailedResume,
+    failure::{classification, failure_kind, FailureKind},
+    grouped_logs,
+    page_scrapers::PageDataSerde,
+    progress::{print_grouped_logs, progress, WebsiteProgress},
+    resume_gen::{language::{Translation, DEFAULT_LANGUAGE}, selection::{Selection, SelectionLimits}, dry_run_preview, unix_to_datetime, GeneratedResume, OUTPUT_PATH, Regexes},
+    tab_pool::{SharedBrowser, TabPool},
+    KeywordExtractor, PageData, ResumeData, ResumeTemplate, Timings
+};
+
+use self::{
+    discovery::{career_site_websites, company_board_websites, new_feed_items},
+    render::{named_template, render_base_resume, render_stage, Localization, RenderJob, RenderSettings},
+    scrape::{enabled_scrapers, fetch_html, filtered, full_page_screenshot, incomplete, is_static, rename_company, save_debug_artifacts, scrape_html, truncate_html},
+    summary::{notify, print_summary, upload_resumes, write_json_output}
+};
+
+mod discovery;
+pub mod render;
+pub mod scrape;
+mod summary;
+
+const CACHE_PATH: &str = ".cache/";
+const DEFAULT_TAB_POOL_SIZE: usize = 4;
+const DEFAULT_MAX_RETRIES: usize = 2;
+const DEFAULT_MIN_COMPLETENESS: f32 = 0.9;
+/// How many seconds navigating to a website or rendering a resume can take by default
+pub const DEFAULT_TIMEOUT_SECS: f64 = 60.0;
+/// Stored in the cache directory, listing the websites that failed and have not succeeded since
+pub const FAILED_WEBSITES_FILE_NAME: &str = "failed-websites.toml";
+/// Kept in the cache directory, with every item of `job_feeds` that was added to a previous run
+const SEEN_FEED_ITEMS_FILE_NAME: &str = "seen-feed-items.toml";
+/// The folder in the output directory that `--debug-artifacts` are saved into
+const DEBUG_DIR_NAME: &str = "debug";
+pub const DEFAULT_MAX_HTML_BYTES: usize = 8 * 1024 * 1024;
+pub const USER_AGENT: &str = concat!("resume-builder/", env!("CARGO_PKG_VERSION"));
+
+
+/// The command line options that change how websites are run
+#[derive(Clone, Default)]
+pub struct RunOptions {
+    /// Print what would be put on each resume without rendering or writing anything
+    pub dry_run: bool,
+    /// Only process websites that have already been cached, skipping the rest
+    pub offline: bool,
+    /// Ignore and replace every cached website
+    pub refresh: bool,
+    /// Ignore and replace the cache of these websites
+    pub refresh_url: Vec<Url>,
+    /// The language of resumes that do not give their own, instead of `resume_language`
+    pub language: Option<String>,
+    /// Show what was chosen to be put on each resume, and toggle entries on or off before it is rendered
+    pub review: bool,
+    /// Run the scrapers again over the HTML saved in the cache, without fetching anything
+    pub rescrape_cached: bool,
+    /// Only use these scrapers, ignoring `omit_default_scrapers` and `enable_optional_scrapers`
+    pub scrapers: Vec<String>,
+    /// Do not use these scrapers, in addition to `omit_default_scrapers`
+    pub omit_scrapers: Vec<String>,
+    /// Overrides `cache_dir` in config.toml
+    pub cache_dir: Option<PathBuf>,
+    /// Overrides `output_dir` in config.toml
+    pub output_dir: Option<PathBuf>,
+    /// Report how long each phase took for every website, and in total
+    pub timings: bool,
+    /// Save what is needed to report scraper bugs for websites that could not be scraped
+    pub debug_artifacts: bool,
+    /// Write what happened to every website as JSON into this file
+    pub json_output: Option<PathBuf>
+}
+
+
+/// The contents of `SEEN_FEED_ITEMS_FILE_NAME`
+#[derive(Deserialize, Serialize, Default)]
+struct SeenFeedItems {
+    urls: Vec<Url>
+}
+
+
+/// The contents of `FAILED_WEBSITES_FILE_NAME`
+#[derive(Deserialize, Serialize, Default)]
+pub struct FailedWebsites {
+    pub websites: Vec<JobWebsite>
+}
+
+
+/// The parts of a run that are slow to start, so that the daemon can keep them running between runs
+#[derive(Clone)]
+pub struct Engine {
+    keyword_extractor: KeywordExtractor,
+    browser: Arc<SharedBrowser>
+}
+
+
+impl Engine {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            keyword_extractor: KeywordExtractor::spawn(config.keyword_extraction.clone()),
+            browser: Arc::new(SharedBrowser::new(config.browser.clone()))
+        }
+    }
+
+    /// Like `new`, but the keyword extraction model and Chrome are started straight away
+    pub fn started(config: &Config) -> anyhow::Result<Self> {
+        tracing::info!("Starting the keyword extraction model and Chrome...");
+        let engine = Self {
+            keyword_extractor: KeywordExtractor::spawn_loaded(config.keyword_extraction.clone()),
+            browser: Arc::new(SharedBrowser::new(config.browser.clone()))
+        };
+        engine.browser.get().map_err(failure_kind(FailureKind::Browser))?;
+        Ok(engine)
+    }
+}
+
+
+/// Where `options` and `config` say the cache is, or the platform default
+pub fn cache_dir(options: &RunOptions, config_cache_dir: Option<PathBuf>) -> PathBuf {
+    options.cache_dir
+        .clone()
+        .or(config_cache_dir)
+        .or_else(|| dirs::cache_dir().map(|x| x.join("resume-builder")))
+        .unwrap_or_else(|| CACHE_PATH.into())
+}
+
+
+pub fn output_dir(options: &RunOptions, config_output_dir: Option<PathBuf>) -> PathBuf {
+    options.output_dir
+        .clone()
+        .or(config_output_dir)
+        .or_else(|| dirs::document_dir().map(|x| x.join("resumes")))
+        .unwrap_or_else(|| OUTPUT_PATH.into())
+}
+
+
+/// Runs every website in `config`, then fails if any of them failed
+pub async fn run(options: &RunOptions, config: Config, engine: Engine) -> anyhow::Result<()> {
+    let results = run_websites(options, config, engine).await?;
+    let failures: Vec<_> = results
+        .iter()
+        .filter_map(|x| if let Outcome::Failed(e) = &x.outcome { Some(e) } else { None })
+        .collect();
+    if !failures.is_empty() {
+        let error = anyhow::anyhow!("{} of {} websites failed. Use `resume-builder retry` to try them again", failures.len(), results.len());
+        let kind = failures
+            .iter()
+            .filter_map(|e| classification(e).and_then(|x| x.kind))
+            .max_by_key(|kind| kind.priority());
+        return Err(match kind {
+            Some(kind) => failure_kind(kind)(error),
+            None => error
+        })
+    }
+    Ok(())
+}
+
+
+/// Scrapes every website in `config` and renders their resumes, then reports what happened to each of them
+///
+/// Only fails if the run as a whole could not go ahead. Websites that failed are in the results instead.
+/// `config.browser` is ignored in favour of the browser in `engine`.
+pub async fn run_websites(options: &RunOptions, config: Config, engine: Engine) -> anyhow::Result<Vec<WebsiteResult>> {
+    let run_start = Instant::now();
+    let output_dir: Arc<Path> = output_dir(options, config.output_dir).into();
+    let cache = Arc::new(Cache::new(cache_dir(options, config.cache_dir), config.cache_backend)?);
+    let cache_ttl = config.cache_ttl_days.map(|x| Duration::from_secs_f64(x * 24.0 * 60.0 * 60.0));
+    let no_data_retry = Duration::from_secs_f64(config.no_data_retry_hours.unwrap_or(24.0) * 60.0 * 60.0);
+
+    config.resume_data.validate().context("resume_data is invalid").map_err(failure_kind(FailureKind::Config))?;
+    config.browser.validate().map_err(failure_kind(FailureKind::Config))?;
+    let resume_data = Arc::new(config.resume_data);
+    let resume_language = config.resume_language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    let default_language = options.language.clone().unwrap_or_else(|| resume_language.clone());
+    Translation::get(&default_language).map_err(failure_kind(FailureKind::Config))?;
+
+    let regexes = Arc::new(Regexes::default());
+    let mut loaded_templates = FxHashMap::<String, ResumeTemplate>::default();
+    let resume_template_name = config.resume_template_path.unwrap_or_else(|| "default".into());
+    let resume_template = named_template(resume_template_name, &config.templates, &config.section_regions, &regexes, &mut loaded_templates).map_err(failure_kind(FailureKind::Template))?;
+
+    let enabled_scrapers = enabled_scrapers(options, config.omit_default_scrapers, config.enable_optional_scrapers);
+    let paragraph_scrapers = Arc::new(config.paragraph_scrapers);
+
+    let Engine { keyword_extractor, browser } = engine;
+    
+    // Scraping and rendering have their own tabs so that neither can starve the other, but share one Chrome
+    let tab_pool_size = config.tab_pool_size.unwrap_or(DEFAULT_TAB_POOL_SIZE);
+    let scrape_tab_pool = Arc::new(TabPool::new(tab_pool_size, browser.clone()));
+    let render_tab_pool = Arc::new(TabPool::new(tab_pool_size, browser));
+    let http_client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    let max_html_bytes = config.max_html_bytes.unwrap_or(DEFAULT_MAX_HTML_BYTES);
+
+    let dry_run = options.dry_run;
+    if !dry_run {
+        DirBuilder::new().recursive(true).create(&output_dir).context(format!("Failed to create resumes directory {output_dir:?}. Do we have permissions?"))?;
+    }
+    let scrape_semaphore = Arc::new(Semaphore::new(config.max_concurrent_scrapes.unwrap_or(Semaphore::MAX_PERMITS)));
+    let max_concurrent_renders = config.max_concurrent_renders.unwrap_or(Semaphore::MAX_PERMITS);
+    let max_retries = config.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+    let selection_limits = SelectionLimits {
+        max_experience_entries: config.max_experience_entries,
+        max_bullets_per_entry: config.max_bullets_per_entry,
+        education_order: config.education_order,
+        drop_education_entries: config.drop_education_entries
+    };
+    let selection_strategy = config.selection_strategy;
+    let min_completeness = config.min_completeness.unwrap_or(DEFAULT_MIN_COMPLETENESS);
+    let job_filter = Arc::new(config.job_filter);
+    let translation_backend = config.translation_backend.map(Arc::new);
+    let company_names = Arc::new(config.company_names);
+    let navigation_timeout = Duration::from_secs_f64(config.navigation_timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let render_timeout = Duration::from_secs_f64(config.render_timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let mut results = vec![];
+    
+    if config.variants.len() > 26 {
+        return Err(failure_kind(FailureKind::Config)(anyhow::anyhow!("At most 26 variants can be given, one for each letter")))
+    }
+    let mut variants = vec![];
+    for variant in config.variants {
+        let template = match &variant.template {
+            Some(name) => Some(named_template(name.clone(), &config.templates, &config.section_regions, &regexes, &mut loaded_templates).map_err(failure_kind(FailureKind::Template))?),
+            None => None
+        };
+        variants.push((variant, template));
+    }
+    // Every website, in the order they were given, so that the summary is in the same order
+    let mut seen_urls = FxHashMap::<Url, usize>::default();
+    let mut job_websites = FxHashMap::<Url, JobWebsite>::default();
+    let refresh_urls: FxHashSet<Url> = options.refresh_url.iter().map(canonicalize_url).collect();
+
+    let board_websites = company_board_websites(&config.company_boards, config.company_board_title_regex.as_deref(), &job_filter, &http_client, options.offline).await?;
+    let seen_feed_items_path = cache.dir().join(SEEN_FEED_ITEMS_FILE_NAME);
+    let mut seen_feed_items = match std::fs::read_to_string(&seen_feed_items_path) {
+        Ok(seen) => toml::from_str::<SeenFeedItems>(&seen)?.urls,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(anyhow::Error::new(e).context(format!("Failed to read {seen_feed_items_path:?}. Do we have permissions?")))
+    };
+    let new_feed_items = new_feed_items(&config.job_feeds, &seen_feed_items, &job_filter, &http_client, options.offline).await;
+    let career_site_websites = career_site_websites(&config.career_sites, &job_filter, &http_client, options.offline).await;
+
+    let mut websites = vec![];
+    // Websites that were found instead of given, which only get resumes if `job_filter` wants them
+    let mut ingested_urls = FxHashSet::<Url>::default();
+    let ingested_websites = board_websites
+        .into_iter()
+        .chain(career_site_websites)
+        .chain(new_feed_items.iter().cloned().map(JobWebsiteEntry::Url));
+    for (website, ingested) in config.job_requirement_websites
+        .into_iter()
+        .map(|x| (x, false))
+        .chain(ingested_websites.map(|x| (x, true)))
+    {
+        let mut website: JobWebsite = website.into();
+        website.url = canonicalize_url(&website.url);
+        if seen_urls.contains_key(&website.url) {
+            tracing::warn!("Skipping {} as it was already given", website.url);
+            continue;
+        }
+        if ingested {
+            ingested_urls.insert(website.url.clone());
+        }
+        seen_urls.insert(website.url.clone(), seen_urls.len());
+        job_websites.insert(website.url.clone(), website.clone());
+        websites.push(website);
+    }
+    
+    let overall_progress = progress().add(ProgressBar::new(websites.len() as u64));
+    overall_progress.set_style(ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos}/{len} websites").unwrap());
+
+    let render_settings = Arc::new(RenderSettings {
+        output_dir: output_dir.clone(),
+        output_layout: config.output_layout,
+        paper: config.paper,
+        render_backend: config.render_backend,
+        render_program: config.render_program.map(Into::into),
+        annotate_postings: config.annotate_postings,
+        dedupe: config.dedupe_jobs.unwrap_or(false),
+        review: options.review,
+        keep_old_resumes: config.keep_old_resumes.unwrap_or(true),
+        selection_limits,
+        selection_strategy,
+        variants,
+        redaction: config.redaction,
+        render_timeout,
+        localization: Localization {
+            resume_language,
+            backend: translation_backend,
+            http_client: http_client.clone(),
+            translated: Default::default()
+        }
+    });
+
+    // Websites that fail in a way that might not happen again are tried again in another round, up to `max_retries` times
+    for attempt in 0.. {
+        let mut round_results = vec![];
+        let mut scrape_tasks = JoinSet::<Option<WebsiteResult>>::new();
+        let (render_sender, render_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let render_task = tokio::spawn(render_stage(
+            render_receiver,
+            render_tab_pool.clone(),
+            resume_data.clone(),
+            regexes.clone(),
+            max_concurrent_renders,
+            render_settings.clone()
+        ));
+
+        for JobWebsite { url, template, output_name, language } in websites {
+            let url = Arc::new(url);
+            let language = language.unwrap_or_else(|| default_language.clone());
+            if let Err(e) = Translation::get(&language) {
+                round_results.push(WebsiteResult::new(&Website::new(url, false), Outcome::Failed(failure_kind(FailureKind::Config)(e)), Timings::default()));
+                continue;
+            }
+            let progress = WebsiteProgress::new(&overall_progress, &url);
+
+            let resume_template = match template {
+                None => resume_template.clone(),
+                Some(name) => match named_template(name, &config.templates, &config.section_regions, &regexes, &mut loaded_templates) {
+                    Ok(template) => template,
+                    Err(e) => {
+                        round_results.push(WebsiteResult::new(&Website::new(url, false), Outcome::Failed(failure_kind(FailureKind::Template)(e)), Timings::default()));
+                        continue;
+                    }
+                }
+            };
+            let refresh = options.refresh || refresh_urls.contains(&*url);
+            let cache_entry = if refresh {
+                None
+            } else {
+                match cache.get(&url).await {
+                    Ok(cache_entry) => cache_entry,
+                    Err(e) => {
+                        round_results.push(WebsiteResult::new(&Website::new(url, false), Outcome::Failed(e), Timings::default()));
+                        continue;
+                    }
+                }
+            };
+            // Websites cannot be scraped again when offline, so stale entries are better than none
+            let cache_entry = cache_entry.filter(|entry| options.offline || entry.is_fresh(cache_ttl, no_data_retry, enabled_scrapers.iter()));
+            let website = Arc::new(Website::new(url.clone(), cache_entry.is_some()));
+            let job_filter = ingested_urls.contains(&*url).then(|| job_filter.clone());
+
+            if let Some(mut cache_entry) = cache_entry {
+                let keyword_extractor = keyword_extractor.clone();
+                let cache = cache.clone();
+                let resume_data = resume_data.clone();
+                let render_sender = render_sender.clone();
+                let company_names = company_names.clone();
+                let enabled_scrapers = enabled_scrapers.clone();
+                let paragraph_scrapers = paragraph_scrapers.clone();
+                let output_dir = output_dir.clone();
+                let rescrape = options.rescrape_cached;
+                let debug_artifacts = options.debug_artifacts;
+
+                let span = info_span!("scrape", url = %url);
+                scrape_tasks.spawn(website_task(website.clone(), FailureKind::Scrape, async move {
+                    let mut timings = Timings::default();
+                    if rescrape {
+                        match cache_entry.html() {
+                            Some(html) => {
+                                progress.set_phase("extracting keywords");
+                                let html = truncate_html(html?, max_html_bytes, &url);
+                                let (page_data, errors, html, scrape_timings) = scrape_html(html, url.clone(), keyword_extractor, enabled_scrapers.clone(), paragraph_scrapers).await;
+                                timings += scrape_timings;
+                                if debug_artifacts && (page_data.is_none() || !errors.is_empty()) {
+                                    if let Err(e) = save_debug_artifacts(&output_dir.join(DEBUG_DIR_NAME), &url, &html, &errors, None).await {
+                                        tracing::warn!("Failed to save debug artifacts: {e:?}");
+                                    }
+                                }
+                                cache_entry.page_data = page_data.map(PageDataSerde::from);
+                                cache_entry.scrapers = enabled_scrapers.iter().cloned().collect();
+                                cache_entry.scrapers.sort();
+                                if !dry_run {
+                                    cache.insert(&url, &cache_entry).await?;
+                                }
+                            }
+                            None => tracing::warn!("{url} was cached without its HTML, so it cannot be rescraped. Consider using --refresh-url {url}")
+                        }
+                    }
+                    // The rest of the entry, including its HTML, is dropped here instead of living until the resume is rendered
+                    let Some(page_data) = cache_entry.page_data else { return Ok(Some((Outcome::NoPageData, timings))) };
+                    let mut page_data = PageData::from(page_data);
+                    rename_company(&mut page_data, &company_names);
+                    website.set_job(&page_data, &resume_data);
+                    if let Some(outcome) = incomplete(&page_data, min_completeness).or_else(|| filtered(&page_data, job_filter.as_deref())) {
+                        return Ok(Some((outcome, timings)))
+                    }
+                    if dry_run {
+                        let preview = dry_run_preview(&page_data, &resume_data, selection_limits, selection_strategy);
+                        progress().suspend(|| print!("{preview}"));
+                        return Ok(Some((Outcome::DryRun, timings)))
+                    }
+                    progress.set_phase("waiting to render");
+                    let _ = render_sender.send(RenderJob { website, page_data, resume_template, output_name, language, timings, progress });
+                    Ok(None)
+                }.instrument(span)));
+                continue;
+            }
+
+            if options.offline {
+                round_results.push(WebsiteResult::new(&website, Outcome::NotCached, Timings::default()));
+                continue;
+            }
+
+            if url.scheme() == "http" {
+                tracing::warn!("You are attempting to scrape {url} without https. Consider modifying the URL to use https instead.");
+            }
+
+            let is_static = is_static(&url, &enabled_scrapers, &config.static_hosts);
+
+            // Wait here instead of in the task so that tasks are not spawned for websites that cannot be scraped yet
+            let scrape_permit = scrape_semaphore.clone().acquire_owned().await?;
+            let tab_pool = scrape_tab_pool.clone();
+            let http_client = http_client.clone();
+            let keyword_extractor = keyword_extractor.clone();
+            let cache = cache.clone();
+            let resume_data = resume_data.clone();
+            let render_sender = render_sender.clone();
+            let company_names = company_names.clone();
+            let enabled_scrapers = enabled_scrapers.clone();
+            let paragraph_scrapers = paragraph_scrapers.clone();
+            let output_dir = output_dir.clone();
+            let debug_artifacts = options.debug_artifacts;
+
+            let span = info_span!("scrape", url = %url);
+            scrape_tasks.spawn(website_task(website.clone(), FailureKind::Scrape, async move {
+                let mut timings = Timings::default();
+                let start = Instant::now();
+                progress.set_phase("navigating");
+                let (html, tab) = fetch_html(&url, is_static, &http_client, &tab_pool, navigation_timeout, &enabled_scrapers).await?;
+                // Released as soon as possible so that other websites can use it,
+                // unless a screenshot may need to be taken once the page has been scraped
+                let mut debug_tab = tab.filter(|_| debug_artifacts);
+                timings.navigation = start.elapsed();
+                let html = truncate_html(html, max_html_bytes, &url);
+
+                progress.set_phase("extracting keywords");
+                let (page_data, errors, html, scrape_timings) = scrape_html(html, url.clone(), keyword_extractor, enabled_scrapers.clone(), paragraph_scrapers).await;
+                timings += scrape_timings;
+                if debug_artifacts && (page_data.is_none() || !errors.is_empty()) {
+                    let screenshot = match debug_tab.take() {
+                        Some(tab) => {
+                            let tab = tab.tab();
+                            tokio_rayon::spawn(move || full_page_screenshot(&tab))
+                                .await
+                                .map_err(|e| tracing::warn!("Failed to take a screenshot: {e:?}"))
+                                .ok()
+                        }
+                        None => None
+                    };
+                    if let Err(e) = save_debug_artifacts(&output_dir.join(DEBUG_DIR_NAME), &url, &html, &errors, screenshot).await {
+                        tracing::warn!("Failed to save debug artifacts: {e:?}");
+                    }
+                }
+                drop(debug_tab);
+                drop(scrape_permit);
+
+                let cache_entry = CacheEntry::new(&url, page_data.map(PageDataSerde::from), &html, enabled_scrapers.iter());
+                drop(html);
+                if !dry_run {
+                    cache.insert(&url, &cache_entry).await?;
+                }
+                // Drops the compressed HTML before waiting to render
+                let Some(page_data) = cache_entry.page_data else {
+                    return Ok(Some((Outcome::NoPageData, timings)))
+                };
+                let mut page_data = PageData::from(page_data);
+                rename_company(&mut page_data, &company_names);
+                website.set_job(&page_data, &resume_data);
+                if let Some(outcome) = incomplete(&page_data, min_completeness).or_else(|| filtered(&page_data, job_filter.as_deref())) {
+                    return Ok(Some((outcome, timings)))
+                }
+                if dry_run {
+                    let preview = dry_run_preview(&page_data, &resume_data, selection_limits, selection_strategy);
+                    progress().suspend(|| print!("{preview}"));
+                    return Ok(Some((Outcome::DryRun, timings)))
+                }
+                progress.set_phase("waiting to render");
+                let _ = render_sender.send(RenderJob { website, page_data, resume_template, output_name, language, timings, progress });
+                Ok(None)
+            }.instrument(span)));
+        }
+        // The render stage finishes once every scrape task has dropped its sender
+        drop(render_sender);
+
+        while let Some(result) = scrape_tasks.join_next().await {
+            round_results.extend(result?);
+        }
+        round_results.extend(render_task.await??);
+
+        let (retry, done): (Vec<_>, Vec<_>) = round_results
+            .into_iter()
+            .partition(|result| attempt < max_retries && result.is_transient());
+        results.extend(done);
+        if retry.is_empty() {
+            break
+        }
+        tracing::warn!("Retrying {} websites that failed (retry {} of {max_retries})", retry.len(), attempt + 1);
+        overall_progress.inc_length(retry.len() as u64);
+        websites = retry.iter().map(|result| job_websites[&*result.url].clone()).collect();
+    }
+    overall_progress.finish_and_clear();
+    // Such as lines logged for a website after its progress bar was dropped
+    for (url, lines) in grouped_logs::take_all() {
+        print_grouped_logs(&url, &lines);
+    }
+
+    if config.base_resume.unwrap_or(true) && !dry_run {
+        let translation = Translation::get(&default_language).map_err(failure_kind(FailureKind::Config))?;
+        let render = render_base_resume(&render_tab_pool, resume_data.clone(), resume_template, translation, regexes, &render_settings);
+        match tokio::time::timeout(render_timeout, render).await {
+            Ok(Ok(resume)) if !resume.up_to_date => tracing::info!("Rendered the base resume into {:?}", resume.path),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!("Failed to render the base resume: {e:?}"),
+            Err(_) => tracing::warn!("Rendering the base resume took longer than {render_timeout:?}")
+        }
+    }
+    results.sort_by_key(|result| seen_urls.get(&*result.url).copied());
+
+    if let Some(path) = &options.json_output {
+        write_json_output(path, &results)?;
+    }
+
+    if options.timings {
+        let mut total_timings = Timings::default();
+        for result in &results {
+            println!("Timings for {}: {}", result.url, result.timings);
+            total_timings += result.timings;
+        }
+        println!("Total timings: {total_timings}");
+        println!("Finished all websites in {:.2}s", run_start.elapsed().as_secs_f64());
+    }
+
+    print_summary(&results);
+    notify(&config.notifications, &http_client, &results).await;
+    if !dry_run {
+        // Runs with only some of the websites, such as `retry` or the daemon, keep the failures of the websites they did not run
+        let path = cache.dir().join(FAILED_WEBSITES_FILE_NAME);
+        let previously_failed = match std::fs::read_to_string(&path) {
+            Ok(failed) => toml::from_str::<FailedWebsites>(&failed).map(|x| x.websites).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring the websites that failed before, as {path:?} is invalid: {e}");
+                vec![]
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(anyhow::Error::new(e).context(format!("Failed to read {path:?}. Do we have permissions?")))
+        };
+        let failed = FailedWebsites {
+            websites: previously_failed
+                .into_iter()
+                .filter(|x| !job_websites.contains_key(&x.url))
+                .chain(
+                    results
+                        .iter()
+                        .filter(|x| matches!(x.outcome, Outcome::Failed(_)))
+                        .map(|x| job_websites[&*x.url].clone())
+                )
+                .collect()
+        };
+        std::fs::write(&path, toml::to_string(&failed)?).context(format!("Failed to write {path:?}. Do we have permissions?"))?;
+
+        if !new_feed_items.is_empty() {
+            // Feed items that failed are in failed-websites.toml, so they can still be tried again with `retry`
+            seen_feed_items.extend(new_feed_items);
+            let seen = SeenFeedItems { urls: seen_feed_items };
+            std::fs::write(&seen_feed_items_path, toml::to_string(&seen)?).context(format!("Failed to write {seen_feed_items_path:?}. Do we have permissions?"))?;
+        }
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let today = unix_to_datetime(now).date.map(|x| x.to_string()).unwrap_or_default();
+        let applications = results
+            .iter()
+            .filter_map(|result| match (&result.outcome, &result.job) {
+                (Outcome::Rendered(resume), Some(job)) => Some(Application::new(
+                    today.clone(),
+                    job.company.clone(),
+                    job.job_title.clone(),
+                    result.url.to_string(),
+                    job.match_score,
+                    resume.path.display().to_string()
+                )),
+                _ => None
+            })
+            .collect();
+        // The resumes were already written, so failing to track them should not fail the run
+        if let Err(e) = applications::record(&output_dir.join(APPLICATIONS_FILE_NAME), applications) {
+            tracing::warn!("{e:?}");
+        }
+        upload_resumes(&config.uploads, &http_client, &output_dir, &results).await;
+        if let Some(email) = &config.email {
+            let resumes: Vec<_> = results
+                .iter()
+                .filter_map(|result| match (&result.outcome, &result.job) {
+                    // Resumes that were already up to date were emailed in a previous run
+                    (Outcome::Rendered(resume), Some(job)) if !resume.up_to_date => Some(EmailedResume {
+                        url: result.url.to_string(),
+                        company: job.company.clone(),
+                        job_title: job.job_title.clone(),
+                        match_score: job.match_score,
+                        path: resume.path.clone()
+                    }),
+                    _ => None
+                })
+                .collect();
+            if let Err(e) = email.send(&resumes).await {
+                tracing::warn!("{e:?}");
+            }
+        }
+    }
+    if !dry_run && results.iter().all(|x| !matches!(x.outcome, Outcome::Failed(_))) {
+        tracing::info!("Resumes completed successfully! They can be found in {output_dir:?}");
+    }
+    Ok(results)
+}
+
+
+/// What happened to a website during a run
+pub enum Outcome {
+    /// Its resume was written, or was already up to date
+    Rendered(GeneratedResume),
+    /// What would be on its resume was printed instead
+    DryRun,
+    /// Nothing could be scraped from it
+    NoPageData,
+    /// Too little could be scraped from it to tailor a resume, such as its company or job title
+    Incomplete(Vec<&'static str>),
+    /// It was found instead of given, and is not wanted by `job_filter` for the given reason
+    Filtered(String),
+    /// It was skipped as it has not been cached, and this run is offline
+    NotCached,
+    /// It is the same job as the given website, so it was merged into its resume
+    Duplicate(Arc<Url>),
+    Failed(anyhow::Error)
+}
+
+
+impl Outcome {
+    pub fn status(&self) -> &'static str {
+        match self {
+            Outcome::Rendered(..) => "rendered",
+            Outcome::DryRun => "dry run",
+            Outcome::NoPageData => "no page data",
+            Outcome::Incomplete(_) => "incomplete",
+            Outcome::Filtered(_) => "filtered",
+            Outcome::Duplicate(_) => "duplicate",
+            Outcome::NotCached => "not cached",
+            Outcome::Failed(_) => "failed"
+        }
+    }
+}
+
+
+/// What has been learned about a website so far, shared between its scrape and render tasks
+struct Website {
+    url: Arc<Url>,
+    /// Whether the website was found in the cache instead of being scraped
+    cached: bool,
+    job: OnceLock<JobSummary>
+}
+
+
+/// The parts of a website's `PageData` that are reported in `--json-output`
+#[derive(Serialize, Clone)]
+pub struct JobSummary {
+    company: String,
+    job_title: String,
+    scrapers: Vec<String>,
+    keyword_count: usize,
+    match_score: f32
+}
+
+
+impl Website {
+    fn new(url: Arc<Url>, cached: bool) -> Self {
+        Self { url, cached, job: OnceLock::new() }
+    }
+
+    fn set_job(&self, page_data: &PageData, resume_data: &ResumeData) {
+        let _ = self.job.set(JobSummary {
+            company: page_data.company.clone(),
+            job_title: page_data.job_title.clone(),
+            scrapers: page_data.scrapers.clone(),
+            keyword_count: page_data.keywords.len(),
+            // Limits and strategies do not change how much of the resume data matches the job
+            match_score: Selection::new(resume_data, page_data, Default::default(), Default::default()).match_score
+        });
+    }
+}
+
+
+/// The outcome of a single website, along with how long it took
+pub struct WebsiteResult {
+    pub url: Arc<Url>,
+    cached: bool,
+    pub job: Option<JobSummary>,
+    pub outcome: Outcome,
+    timings: Timings
+}
+
+
+impl WebsiteResult {
+    fn new(website: &Website, outcome: Outcome, timings: Timings) -> Self {
+        Self { url: website.url.clone(), cached: website.cached, job: website.job.get().cloned(), outcome, timings }
+    }
+
+    /// Whether this website failed in a way that might not happen again
+    fn is_transient(&self) -> bool {
+        matches!(&self.outcome, Outcome::Failed(e) if classification(e).is_some_and(|x| x.transient))
+    }
+}
+
+
+/// The outcome of a website's task, or `None` if it was handed off to another stage
+type TaskOutcome = anyhow::Result<Option<(Outcome, Timings)>>;
+
+
+/// Runs the given future on its own task, so that errors and panics only fail the given website
+///
+/// Errors are given `kind` unless they already have one.
+async fn website_task(website: Arc<Website>, kind: FailureKind, future: impl std::future::Future<Output = TaskOutcome> + Send + 'static) -> Option<WebsiteResult> {
+    let (outcome, timings) = match tokio::spawn(future).await {
+        Ok(Ok(outcome)) => outcome?,
+        Ok(Err(e)) => (Outcome::Failed(failure_kind(kind)(e)), Timings::default()),
+        Err(e) => (Outcome::Failed(failure_kind(kind)(anyhow::anyhow!("{e}"))), Timings::default())
+    };
+    if let Outcome::Failed(e) = &outcome {
+        tracing::error!("Failed to process {}: {e:?}", website.url);
+    }
+    Some(WebsiteResult::new(&website, outcome, timings))
+}
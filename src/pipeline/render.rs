@@ -0,0 +1,302 @@
+//! Rendering the resumes of websites once they have been scraped
+use std::{path::{Path, PathBuf}, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use fxhash::FxHashMap;
+use tokio::{task::JoinSet, sync::Semaphore};
+use tracing::{Instrument, info_span};
+use url::Url;
+
+use crate::{
+    config::{variant_name, Variant},
+    dedupe::is_same_job,
+    failure::{failure_kind, render_error, tab_error, transient, FailureKind},
+    progress::{progress, WebsiteProgress},
+    resume_gen::{annotate::{write_annotated_posting, ANNOTATED_POSTING_FILE_NAME}, backend::RenderBackend, diff::BASE_RESUME, language::{Translation, TranslationBackend}, layout::{OutputLayout, ResumePaths}, paper::Paper, redact::{Redaction, REDACTED_FOLDER_NAME}, review::review_selection, selection::{Selection, SelectionLimits, Strategy}, GeneratedResume, Regexes, RenderRequest},
+    tab_pool::{PooledTab, TabPool},
+    PageData, ResumeData, ResumeTemplate, Timings, generate_resume
+};
+
+use super::{website_task, Outcome, Website, WebsiteResult};
+
+
+/// A website that has been scraped (or found in the cache) and is waiting for its resume to be rendered
+pub(super) struct RenderJob {
+    pub(super) website: Arc<Website>,
+    pub(super) page_data: PageData,
+    pub(super) resume_template: ResumeTemplate,
+    pub(super) output_name: Option<String>,
+    /// A language with a bundled `Translation`
+    pub(super) language: String,
+    /// How long the website took to scrape
+    pub(super) timings: Timings,
+    pub(super) progress: WebsiteProgress
+}
+
+
+/// What every resume of a run is rendered with, from config.toml and the command line
+pub(super) struct RenderSettings {
+    pub(super) output_dir: Arc<Path>,
+    pub(super) output_layout: OutputLayout,
+    pub(super) paper: Paper,
+    pub(super) render_backend: RenderBackend,
+    pub(super) render_program: Option<Arc<Path>>,
+    /// Whether a posting_annotated.html is written next to each resume
+    pub(super) annotate_postings: bool,
+    /// Whether rendering waits for every website to be scraped, so that websites for the same job
+    /// can be merged into one resume
+    pub(super) dedupe: bool,
+    /// Whether what is chosen for each resume can be changed in the terminal before it is rendered
+    pub(super) review: bool,
+    pub(super) keep_old_resumes: bool,
+    pub(super) selection_limits: SelectionLimits,
+    pub(super) selection_strategy: Strategy,
+    /// Each variant, along with its own template if it has one
+    pub(super) variants: Vec<(Variant, Option<ResumeTemplate>)>,
+    pub(super) redaction: Option<Redaction>,
+    pub(super) render_timeout: Duration,
+    pub(super) localization: Localization
+}
+
+
+/// How `render_stage` puts resumes into other languages
+pub(super) struct Localization {
+    /// The language that the resume data is written in
+    pub(super) resume_language: String,
+    pub(super) backend: Option<Arc<TranslationBackend>>,
+    pub(super) http_client: reqwest::Client,
+    /// The resume data translated into each language, so that it is only translated once per run
+    pub(super) translated: Arc<tokio::sync::Mutex<FxHashMap<String, Arc<ResumeData>>>>
+}
+
+
+impl Localization {
+    /// The resume data in the given language, translated if there is a backend to translate it with
+    async fn resume_data(&self, resume_data: Arc<ResumeData>, language: &str) -> anyhow::Result<Arc<ResumeData>> {
+        let Some(backend) = &self.backend else { return Ok(resume_data) };
+        if language.eq_ignore_ascii_case(&self.resume_language) {
+            return Ok(resume_data)
+        }
+        // Held while translating, so that other resumes in the same language wait instead of translating it again
+        let mut translated = self.translated.lock().await;
+        if let Some(resume_data) = translated.get(language) {
+            return Ok(resume_data.clone())
+        }
+        tracing::info!("Translating the resume data into {language:?}");
+        let resume_data = Arc::new(backend.translate_resume_data(&self.http_client, &resume_data, language).await?);
+        translated.insert(language.to_string(), resume_data.clone());
+        Ok(resume_data)
+    }
+}
+
+
+/// Loads the template with the given name in `templates`, `"default"`, `"sidebar"`, or at the given path,
+/// with its sections moved to `section_regions`, reusing it if it was already loaded
+pub fn named_template(name: String, templates: &FxHashMap<String, PathBuf>, section_regions: &FxHashMap<String, String>, regexes: &Regexes, loaded_templates: &mut FxHashMap<String, ResumeTemplate>) -> anyhow::Result<ResumeTemplate> {
+    if let Some(template) = loaded_templates.get(&name) {
+        return Ok(template.clone())
+    }
+    let template = match name.as_str() {
+        "default" => ResumeTemplate::Default,
+        "sidebar" => ResumeTemplate::Sidebar,
+        _ => ResumeTemplate::load(templates.get(&name).map(PathBuf::as_path).unwrap_or(Path::new(&name)), regexes)?
+    };
+    let template = template.with_regions(section_regions)?;
+    loaded_templates.insert(name, template.clone());
+    Ok(template)
+}
+
+
+/// Merges jobs that are the same job as an earlier one into that job
+///
+/// Returns the jobs that are left, and the results of the ones that were merged.
+fn merge_duplicates(jobs: Vec<RenderJob>) -> (Vec<RenderJob>, Vec<WebsiteResult>) {
+    let mut merged: Vec<RenderJob> = vec![];
+    let mut duplicates = vec![];
+    for job in jobs {
+        match merged.iter_mut().find(|x| is_same_job(&x.page_data, &job.page_data)) {
+            Some(primary) => {
+                let RenderJob { website, page_data, timings, .. } = job;
+                tracing::info!("{} is the same job as {}, so only one resume will be generated for them", website.url, primary.website.url);
+                primary.page_data = primary.page_data.clone() + page_data;
+                duplicates.push(WebsiteResult::new(&website, Outcome::Duplicate(primary.website.url.clone()), timings));
+            }
+            None => merged.push(job)
+        }
+    }
+    (merged, duplicates)
+}
+
+
+/// Renders the resumes of websites as they finish scraping, with at most `max_concurrent_renders` at once
+///
+/// Returns once the sender has been dropped and every resume has been rendered.
+pub(super) async fn render_stage(
+    mut receiver: tokio::sync::mpsc::UnboundedReceiver<RenderJob>,
+    tab_pool: Arc<TabPool>,
+    resume_data: Arc<ResumeData>,
+    regexes: Arc<Regexes>,
+    max_concurrent_renders: usize,
+    settings: Arc<RenderSettings>
+) -> anyhow::Result<Vec<WebsiteResult>> {
+    let semaphore = Arc::new(Semaphore::new(max_concurrent_renders));
+    let mut render_tasks = JoinSet::<Option<WebsiteResult>>::new();
+
+    let spawn_render = |render_tasks: &mut JoinSet<_>, job: RenderJob, selection: Selection| {
+        let semaphore = semaphore.clone();
+        let tab_pool = tab_pool.clone();
+        let resume_data = resume_data.clone();
+        let regexes = regexes.clone();
+        let settings = settings.clone();
+
+        let span = info_span!("render", url = %job.website.url);
+        render_tasks.spawn(website_task(job.website.clone(), FailureKind::Render, async move {
+            let _permit = semaphore.acquire_owned().await?;
+            let RenderJob { page_data, resume_template, output_name, language, mut timings, progress, .. } = job;
+            let translation = Translation::get(&language).map_err(failure_kind(FailureKind::Config))?;
+            let resume_data = settings.localization.resume_data(resume_data, &language).await.map_err(transient)?;
+            tracing::debug!("Rendering");
+            progress.set_phase("rendering");
+            // Rendering usually happens in Chrome, so it can fail the same ways that navigating can
+            let tab = match settings.render_backend {
+                RenderBackend::Chrome => Some(tab_pool.acquire().map_err(tab_error)?),
+                _ => None
+            };
+
+            // Each variant is rendered into a folder inside the job's folder
+            let mut targets = vec![];
+            let paths = settings.output_layout.paths(&settings.output_dir, &page_data, output_name.as_deref());
+            if settings.variants.is_empty() {
+                targets.push((paths, selection, resume_template));
+            } else {
+                for (i, (variant, template)) in settings.variants.iter().enumerate() {
+                    let variant_selection = variant.select(selection.clone(), &resume_data);
+                    if let Some((_, first_selection, _)) = targets.first() {
+                        let (added, removed) = variant_selection.note_changes(first_selection);
+                        tracing::info!("{} has {added} notes that {} does not, and leaves out {removed} of its notes", variant_name(i), variant_name(0));
+                    }
+                    targets.push((paths.nested(&variant_name(i)), variant_selection, template.clone().unwrap_or_else(|| resume_template.clone())));
+                }
+            }
+
+            // Each target gets a redacted version in a folder inside its own, which may replace the full version
+            let mut targets: Vec<_> = targets.into_iter().map(|x| (x, resume_data.clone())).collect();
+            if let Some(redaction) = &settings.redaction {
+                let redacted_data = Arc::new(redaction.redact(&resume_data));
+                let redacted_targets: Vec<_> = targets
+                    .iter()
+                    .map(|((paths, selection, template), _)| ((paths.nested(REDACTED_FOLDER_NAME), selection.clone(), template.clone()), redacted_data.clone()))
+                    .collect();
+                if redaction.only_redacted {
+                    targets = redacted_targets;
+                } else {
+                    targets.extend(redacted_targets);
+                }
+            }
+
+            let mut first_resume = None;
+            for ((paths, selection, resume_template), resume_data) in targets {
+                let annotated_selection = settings.annotate_postings.then(|| selection.clone());
+                let renderer = settings.render_backend.renderer(tab.as_ref().map(PooledTab::tab), settings.render_program.clone());
+                let request = RenderRequest { selection, translation, resume_template, paper: settings.paper, paths: &paths, keep_old_versions: settings.keep_old_resumes };
+                let render = generate_resume(page_data.clone(), renderer, resume_data.clone(), regexes.clone(), request);
+                let resume = match tokio::time::timeout(settings.render_timeout, render).await {
+                    Ok(resume) => resume.map_err(render_error)?,
+                    Err(_) => {
+                        if let Some(tab) = tab {
+                            tab.discard();
+                        }
+                        return Err(transient(anyhow::anyhow!("Rendering took longer than {:?}", settings.render_timeout)))
+                    }
+                };
+                if let Some(selection) = annotated_selection {
+                    annotate_posting(&paths, &page_data, &resume_data, &selection, &resume).await;
+                }
+                timings += resume.timings;
+                first_resume.get_or_insert(resume);
+            }
+            tracing::info!("Finished rendering");
+            Ok(Some((Outcome::Rendered(first_resume.context("There should be at least one resume")?), timings)))
+        }.instrument(span)));
+    };
+
+    // Jobs are reviewed one at a time, as they are reviewed in the terminal
+    let select = |job: &RenderJob| {
+        let selection = Selection::new(&resume_data, &job.page_data, settings.selection_limits, settings.selection_strategy);
+        let resume_data = resume_data.clone();
+        let page_data = job.page_data.clone();
+        let review = settings.review;
+        async move {
+            if !review {
+                return anyhow::Ok(selection)
+            }
+            tokio::task::spawn_blocking(move || progress().suspend(|| review_selection(&resume_data, &page_data, selection))).await?
+        }
+    };
+
+    // Duplicates can only be found once every website has been scraped, so rendering waits until then
+    let mut pending = vec![];
+    while let Some(job) = receiver.recv().await {
+        if settings.dedupe {
+            pending.push(job);
+        } else {
+            let selection = select(&job).await?;
+            spawn_render(&mut render_tasks, job, selection);
+        }
+    }
+    let (pending, mut results) = merge_duplicates(pending);
+    for job in pending {
+        let selection = select(&job).await?;
+        spawn_render(&mut render_tasks, job, selection);
+    }
+
+    while let Some(result) = render_tasks.join_next().await {
+        results.extend(result?);
+    }
+    Ok(results)
+}
+
+
+/// Renders a resume with every note into the `BASE_RESUME` folder of the output directory, as a resume for no job in particular
+///
+/// Experience entries and bullets are still limited by `selection_limits`, taking the first ones in config order.
+pub(super) async fn render_base_resume(tab_pool: &Arc<TabPool>, resume_data: Arc<ResumeData>, resume_template: ResumeTemplate, translation: &'static Translation, regexes: Arc<Regexes>, settings: &RenderSettings) -> anyhow::Result<GeneratedResume> {
+    // Without keywords, nothing is more relevant than anything else
+    let page_data = PageData {
+        keywords: Default::default(),
+        url: Arc::new(Url::parse("about:blank")?),
+        job_title: String::new(),
+        company: String::new(),
+        location: None,
+        salary: None,
+        description: String::new(),
+        scraped_at: 0,
+        scrapers: vec![]
+    };
+    let selection = Selection::new(&resume_data, &page_data, settings.selection_limits, settings.selection_strategy).with_all_notes(&resume_data);
+    let folder = settings.output_dir.join(BASE_RESUME);
+    let paths = ResumePaths { resume: folder.join("resume.pdf"), folder };
+    let tab = match settings.render_backend {
+        RenderBackend::Chrome => Some(tab_pool.acquire()?),
+        _ => None
+    };
+    let renderer = settings.render_backend.renderer(tab.as_ref().map(PooledTab::tab), settings.render_program.clone());
+    let request = RenderRequest { selection, translation, resume_template, paper: settings.paper, paths: &paths, keep_old_versions: settings.keep_old_resumes };
+    generate_resume(page_data, renderer, resume_data, regexes, request).await
+}
+
+
+/// Writes a posting_annotated.html next to the given resume
+///
+/// `selection` is only used if the resume did not say what was put on it. Annotating is not worth failing
+/// the resume over, so errors are only logged.
+pub async fn annotate_posting(paths: &ResumePaths, page_data: &PageData, resume_data: &ResumeData, selection: &Selection, resume: &GeneratedResume) {
+    // What fit on an up to date resume is not known, so the existing one is kept
+    if resume.up_to_date && tokio::fs::try_exists(paths.folder.join(ANNOTATED_POSTING_FILE_NAME)).await.unwrap_or(false) {
+        return
+    }
+    let selection = resume.selection.as_ref().unwrap_or(selection);
+    if let Err(e) = write_annotated_posting(&paths.folder, page_data, resume_data, selection).await {
+        tracing::warn!("Failed to annotate the posting: {e:#}");
+    }
+}
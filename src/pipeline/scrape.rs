@@ -0,0 +1,243 @@
+//! Fetching websites and running the scrapers over them
+use std::{path::Path, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use anyhow::Context;
+use fxhash::{FxHashMap, FxHashSet};
+use headless_chrome::{Tab, protocol::cdp::Page};
+use tracing::info_span;
+use url::Url;
+
+use crate::{
+    failure::{tab_error, transient},
+    job_filter::JobFilter,
+    page_scrapers::{company::clean_slug, encoding::decode_html, DEFAULT_SCRAPERS, scrape_page, is_static_page, wait_for_selector},
+    tab_pool::{PooledTab, TabPool},
+    KeywordExtractor, PageData, ScraperState, Timings
+};
+
+use super::{Outcome, RunOptions};
+
+
+/// How long Chrome waits for the content that a scraper needs to load, after navigating to a website
+const CONTENT_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// Cuts the given HTML down to at most `max_bytes`, without splitting a character
+pub fn truncate_html(mut html: String, max_bytes: usize, url: &Url) -> String {
+    if html.len() > max_bytes {
+        tracing::warn!("{url} is {} bytes large, so only the first {max_bytes} bytes will be scraped", html.len());
+        let mut end = max_bytes;
+        while !html.is_char_boundary(end) {
+            end -= 1;
+        }
+        html.truncate(end);
+        html.shrink_to_fit();
+    }
+    html
+}
+
+
+/// Replaces the company name with the one in `company_names`, if there is one
+///
+/// Names are looked up as scraped, then as cleaned up, since websites cached by older versions have slugs instead.
+pub fn rename_company(page_data: &mut PageData, company_names: &FxHashMap<String, String>) {
+    let name = company_names
+        .get(&page_data.company)
+        .or_else(|| company_names.get(&clean_slug(&page_data.company)));
+    if let Some(name) = name {
+        page_data.company = name.clone();
+    }
+}
+
+
+/// Runs the enabled scrapers over the given HTML on the rayon thread pool, reporting any errors
+///
+/// The HTML is handed back so that it can be cached, along with the errors of the scrapers and how long scraping took.
+pub async fn scrape_html(html: String, url: Arc<Url>, keyword_extractor: KeywordExtractor, enabled_scrapers: Arc<FxHashSet<String>>, paragraph_scrapers: Arc<FxHashSet<String>>) -> (Option<PageData>, Vec<String>, String, Timings) {
+    let state = ScraperState {
+        html,
+        url,
+        keyword_extractor,
+        enabled_scrapers,
+        paragraph_scrapers,
+        keyword_extraction_time: Mutex::default()
+    };
+    
+    let start = Instant::now();
+    let span = info_span!("extract");
+    let ((page_data, errors), state) = tokio_rayon::spawn(move || {
+        let _span = span.enter();
+        (scrape_page(&state), state)
+    }).await;
+    let timings = Timings {
+        scraping: start.elapsed(),
+        keyword_extraction: *state.keyword_extraction_time.lock().unwrap(),
+        ..Default::default()
+    };
+
+    let errors: Vec<_> = errors.into_iter().map(|error| format!("{error:?}")).collect();
+    for error in &errors {
+        tracing::error!("{error}");
+    }
+    if page_data.is_none() {
+        tracing::warn!("No Page Data!");
+    }
+    tracing::info!("Finished scraping");
+
+    (page_data, errors, state.html, timings)
+}
+
+
+/// `Outcome::Incomplete` if less than `min_completeness` of the given page data was scraped
+pub(super) fn incomplete(page_data: &PageData, min_completeness: f32) -> Option<Outcome> {
+    let (completeness, missing) = page_data.completeness();
+    if completeness >= min_completeness {
+        return None
+    }
+    tracing::warn!("Skipping {}, as its {} could not be scraped", page_data.url, missing.join(", "));
+    Some(Outcome::Incomplete(missing))
+}
+
+
+/// `Outcome::Filtered` if the given job has a `job_filter` that does not want it
+pub(super) fn filtered(page_data: &PageData, job_filter: Option<&JobFilter>) -> Option<Outcome> {
+    let reason = job_filter?.rejection(page_data)?;
+    tracing::info!("Skipping {}: {reason}", page_data.url);
+    Some(Outcome::Filtered(reason))
+}
+
+
+/// The scrapers given in `options`, or the ones enabled in config.toml
+pub fn enabled_scrapers(options: &RunOptions, omit_default_scrapers: Vec<String>, enable_optional_scrapers: Vec<String>) -> Arc<FxHashSet<String>> {
+    let mut enabled_scrapers: FxHashSet<String> = if options.scrapers.is_empty() {
+        let omit_default_scrapers: FxHashSet<String> = omit_default_scrapers.into_iter().collect();
+
+        DEFAULT_SCRAPERS
+            .into_iter()
+            .filter_map(|x| if omit_default_scrapers.contains(x) {
+                    None
+                } else {
+                    Some(x.to_string())
+                })
+            .chain(enable_optional_scrapers)
+            .collect()
+    } else {
+        options.scrapers.iter().cloned().collect()
+    };
+    for scraper in &options.omit_scrapers {
+        enabled_scrapers.remove(scraper);
+    }
+    Arc::new(enabled_scrapers)
+}
+
+
+/// Whether the given website can be fetched with a plain HTTP request instead of through Chrome
+pub fn is_static(url: &Url, enabled_scrapers: &FxHashSet<String>, static_hosts: &[String]) -> bool {
+    is_static_page(url, enabled_scrapers) || url.host_str().is_some_and(|host| {
+        static_hosts.iter().any(|x| host == x || host.strip_suffix(x.as_str()).is_some_and(|x| x.ends_with('.')))
+    })
+}
+
+
+/// Fetches the HTML of the given website over HTTP if `is_static`, or by navigating to it in Chrome
+///
+/// The tab is given back if Chrome was used, so that a screenshot can still be taken of the page.
+pub async fn fetch_html(
+    url: &Arc<Url>,
+    is_static: bool,
+    http_client: &reqwest::Client,
+    tab_pool: &Arc<TabPool>,
+    navigation_timeout: Duration,
+    enabled_scrapers: &FxHashSet<String>
+) -> anyhow::Result<(String, Option<PooledTab>)> {
+    if is_static {
+        tracing::debug!("Fetching over HTTP");
+        let html = async {
+            let response = http_client.get(url.as_str()).timeout(navigation_timeout).send().await?.error_for_status()?;
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|x| x.to_str().ok())
+                .map(ToString::to_string);
+            // Chrome decodes pages by itself, but reqwest ignores `<meta charset>`
+            let html = response.bytes().await?;
+            reqwest::Result::Ok(decode_html(&html, content_type.as_deref()))
+        }.await.context(format!("Failed to fetch {url}")).map_err(transient)?;
+        return Ok((html, None))
+    }
+
+    tracing::debug!("Navigating in Chrome");
+    let url2 = url.clone();
+    let tab = tab_pool.acquire().map_err(tab_error)?;
+    let tab_handle = tab.tab();
+    tab_handle.set_default_timeout(navigation_timeout);
+    let selector = wait_for_selector(url, enabled_scrapers);
+    let navigation = tokio_rayon::spawn(move || {
+        tab_handle.navigate_to(url2.as_str())?.wait_until_navigated()?;
+        if let Some(selector) = selector {
+            if tab_handle.wait_for_element_with_custom_timeout(selector, CONTENT_WAIT_TIMEOUT).is_err() {
+                tracing::debug!("{selector} never appeared");
+            }
+        }
+        tab_handle.get_content()
+    });
+    match tokio::time::timeout(navigation_timeout, navigation).await {
+        Ok(html) => Ok((html.context(format!("Failed to navigate to {url}")).map_err(transient)?, Some(tab))),
+        Err(_) => {
+            // The tab may still be loading the page, so it cannot be reused
+            tab.discard();
+            Err(transient(anyhow::anyhow!("Navigating to {url} took longer than {navigation_timeout:?}")))
+        }
+    }
+}
+
+
+/// Turns a URL into something that can be used as a folder name
+fn url_to_file_name(url: &Url) -> String {
+    let mut name: String = url
+        .as_str()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    name.truncate(100);
+    name
+}
+
+
+/// Writes the HTML of a website that could not be scraped, the errors of the scrapers, and a screenshot if given,
+/// into a folder for the website in `debug_dir`
+pub(super) async fn save_debug_artifacts(debug_dir: &Path, url: &Url, html: &str, errors: &[String], screenshot: Option<Vec<u8>>) -> anyhow::Result<()> {
+    let dir = debug_dir.join(url_to_file_name(url));
+    tokio::fs::DirBuilder::new().recursive(true).create(&dir).await.context(format!("Failed to create {dir:?}. Do we have permissions?"))?;
+    tokio::fs::write(dir.join("page.html"), html).await?;
+    let mut report = format!("{url}\n\n");
+    if errors.is_empty() {
+        report += "No scraper was applicable to this website, or none of them found anything\n";
+    }
+    for error in errors {
+        report += &format!("{error}\n\n");
+    }
+    tokio::fs::write(dir.join("errors.txt"), report).await?;
+    if let Some(screenshot) = screenshot {
+        tokio::fs::write(dir.join("screenshot.png"), screenshot).await?;
+    }
+    tracing::info!("Saved debug artifacts into {dir:?}");
+    Ok(())
+}
+
+
+/// Takes a screenshot of the whole page in the given tab, not just what fits in the window
+pub(super) fn full_page_screenshot(tab: &Tab) -> anyhow::Result<Vec<u8>> {
+    let size = tab.evaluate("[document.documentElement.scrollWidth, document.documentElement.scrollHeight]", false)?
+        .value
+        .and_then(|x| Some((x.get(0)?.as_f64()?, x.get(1)?.as_f64()?)))
+        .context("Failed to measure the page")?;
+    tab.capture_screenshot(
+        Page::CaptureScreenshotFormatOption::Png,
+        None,
+        Some(Page::Viewport { x: 0.0, y: 0.0, width: size.0, height: size.1, scale: 1.0 }),
+        true
+    )
+}
@@ -0,0 +1,166 @@
+//! Reporting what happened to every website once a run has finished
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::{config::NotificationConfig, resume_gen::GeneratedResume, upload::UploadConfig};
+
+use super::{JobSummary, Outcome, WebsiteResult};
+
+
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    websites: Vec<JsonWebsite<'a>>
+}
+
+
+/// A website in `--json-output`
+#[derive(Serialize)]
+struct JsonWebsite<'a> {
+    url: &'a str,
+    status: &'static str,
+    cached: bool,
+    #[serde(flatten)]
+    job: Option<&'a JobSummary>,
+    output_path: Option<&'a Path>,
+    ats_warnings: &'a [String],
+    error: Option<String>
+}
+
+
+pub(super) fn write_json_output(path: &Path, results: &[WebsiteResult]) -> anyhow::Result<()> {
+    let websites: Vec<_> = results
+        .iter()
+        .map(|result| JsonWebsite {
+            url: result.url.as_str(),
+            status: result.outcome.status(),
+            cached: result.cached,
+            job: result.job.as_ref(),
+            output_path: if let Outcome::Rendered(resume) = &result.outcome { Some(&resume.path) } else { None },
+            ats_warnings: if let Outcome::Rendered(resume) = &result.outcome { &resume.ats_warnings } else { &[] },
+            error: if let Outcome::Failed(e) = &result.outcome { Some(format!("{e:#}")) } else { None }
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&JsonOutput { websites })?;
+    std::fs::write(path, json).context(format!("Failed to write {path:?}. Do we have permissions?"))
+}
+
+
+/// Prints a table of every website, and what happened to it
+pub(super) fn print_summary(results: &[WebsiteResult]) {
+    if results.is_empty() {
+        return
+    }
+    let rows: Vec<_> = results
+        .iter()
+        .map(|result| {
+            let details = match &result.outcome {
+                Outcome::Rendered(resume) => resume.path.display().to_string(),
+                Outcome::DryRun | Outcome::NoPageData => "-".to_string(),
+                Outcome::Duplicate(url) => format!("Same job as {url}"),
+                Outcome::NotCached => "Run without --offline to scrape it".to_string(),
+                Outcome::Incomplete(missing) => format!("Missing its {}", missing.join(", ")),
+                Outcome::Filtered(reason) => reason.clone(),
+                Outcome::Failed(e) => format!("{e:#}")
+            };
+            (result.url.to_string(), result.outcome.status(), details)
+        })
+        .collect();
+    let url_width = rows.iter().map(|(url, ..)| url.len()).max().unwrap_or_default().max(3);
+
+    println!("{:url_width$}  {:12}  Output", "URL", "Status");
+    for (url, status, details) in rows {
+        println!("{url:url_width$}  {status:12}  {details}");
+    }
+
+    let ats_warnings: Vec<_> = results
+        .iter()
+        .filter_map(|result| match &result.outcome {
+            Outcome::Rendered(resume) if !resume.ats_warnings.is_empty() => Some((&result.url, &resume.ats_warnings)),
+            _ => None
+        })
+        .collect();
+    if !ats_warnings.is_empty() {
+        println!("\nATS warnings:");
+        for (url, warnings) in ats_warnings {
+            println!("{url}");
+            for warning in warnings {
+                println!("    - {warning}");
+            }
+        }
+    }
+}
+
+
+/// Uploads every rendered resume to every backend in `uploads`, at the same path as in `output_dir`
+///
+/// Any errors are only logged, as the resumes have already been written.
+pub(super) async fn upload_resumes(uploads: &[UploadConfig], http_client: &reqwest::Client, output_dir: &Path, results: &[WebsiteResult]) {
+    if uploads.is_empty() {
+        return
+    }
+    for result in results {
+        let Outcome::Rendered(GeneratedResume { path, .. }) = &result.outcome else { continue };
+        let name = path.strip_prefix(output_dir).unwrap_or(path);
+        let resume = match tokio::fs::read(path).await {
+            Ok(resume) => resume,
+            Err(e) => {
+                tracing::warn!("Failed to read {path:?} to upload it: {e}");
+                continue
+            }
+        };
+        for upload in uploads {
+            if let Err(e) = upload.upload(http_client, name, resume.clone()).await {
+                tracing::warn!("{e:?}");
+            }
+        }
+    }
+}
+
+
+/// Sends the notifications in `config` with how many websites succeeded and failed
+///
+/// Any errors are only logged, as the run has already finished.
+pub(super) async fn notify(config: &NotificationConfig, http_client: &reqwest::Client, results: &[WebsiteResult]) {
+    if !config.desktop && config.webhook_url.is_none() {
+        return
+    }
+    let rendered = results.iter().filter(|x| matches!(x.outcome, Outcome::Rendered(..))).count();
+    let failed: Vec<_> = results.iter().filter(|x| matches!(x.outcome, Outcome::Failed(_))).collect();
+    let summary = if failed.is_empty() {
+        format!("Generated {rendered} resumes")
+    } else {
+        format!("Generated {rendered} resumes, and {} websites failed", failed.len())
+    };
+
+    if config.desktop {
+        let summary = summary.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            notify_rust::Notification::new()
+                .summary("resume-builder finished")
+                .body(&summary)
+                .show()
+        }).await;
+        if let Ok(Err(e)) = result {
+            tracing::warn!("Failed to show a desktop notification: {e}");
+        }
+    }
+
+    if let Some(webhook_url) = &config.webhook_url {
+        let mut text = format!("resume-builder finished. {summary}");
+        for result in &failed {
+            text += &format!("\n• {}", result.url);
+        }
+        // Slack reads `text`, and Discord reads `content`
+        let body = serde_json::json!({ "text": text, "content": text });
+        let result = http_client.post(webhook_url.as_str())
+            .json(&body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        if let Err(e) = result {
+            tracing::warn!("Failed to send the webhook notification: {e}");
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! The progress bars shown while websites are being processed, and writing logs around them
+use std::{io::{self, Write}, sync::OnceLock, time::Duration};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use url::Url;
+
+use crate::grouped_logs;
+
+
+/// The progress bars of the current run
+pub fn progress() -> &'static MultiProgress {
+    static PROGRESS: OnceLock<MultiProgress> = OnceLock::new();
+    PROGRESS.get_or_init(MultiProgress::new)
+}
+
+
+/// Writes logs to stderr above the progress bars, instead of over them
+pub struct ProgressWriter;
+
+
+impl Write for ProgressWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if grouped_logs::hold_back(buf) {
+            return Ok(buf.len())
+        }
+        progress().suspend(|| io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+
+/// Prints the log lines that were held back for the given website, under its URL
+pub fn print_grouped_logs(url: &str, lines: &[u8]) {
+    progress().suspend(|| {
+        let mut stderr = io::stderr().lock();
+        let _ = writeln!(stderr, "{url}");
+        for line in lines.split_inclusive(|&x| x == b'\n') {
+            let _ = stderr.write_all(b"    ").and_then(|_| stderr.write_all(line));
+        }
+    });
+}
+
+
+/// The progress bar of a single website, which counts towards the overall progress bar once dropped
+///
+/// Its log lines are printed once it is dropped too.
+pub struct WebsiteProgress {
+    bar: ProgressBar,
+    overall: ProgressBar,
+    url: String
+}
+
+
+impl WebsiteProgress {
+    pub fn new(overall: &ProgressBar, url: &Url) -> Self {
+        let bar = progress().insert_before(overall, ProgressBar::new_spinner());
+        bar.set_style(ProgressStyle::with_template("{spinner} {msg:<20} {prefix}").unwrap());
+        bar.set_prefix(url.to_string());
+        bar.set_message("waiting");
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Self { bar, overall: overall.clone(), url: url.to_string() }
+    }
+
+    pub fn set_phase(&self, phase: &'static str) {
+        self.bar.set_message(phase);
+    }
+}
+
+
+impl Drop for WebsiteProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        self.overall.inc(1);
+        if let Some(lines) = grouped_logs::take(&self.url) {
+            print_grouped_logs(&self.url, &lines);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Which keywords are most in demand across many job postings
+use fxhash::FxHashMap;
+
+use crate::{applications::write_csv_row, dedupe::title_words, page_scrapers::{salary::SalaryRange, sections::KeywordSection, PageData}, resume_gen::{selection::resume_text, ResumeData}};
+
+
+/// How much a keyword is in demand
+pub struct KeywordTrend {
+    pub keyword: String,
+    /// How many postings have this keyword
+    pub postings: usize,
+    /// The sum of the scores of this keyword in every posting
+    pub total_score: f32,
+    /// Whether the resume data mentions this keyword
+    pub covered: bool,
+    /// Every section of a posting that this keyword was found in
+    pub sections: Vec<KeywordSection>
+}
+
+
+/// Ranks every keyword in the given postings by how many postings have it, then by their total score
+pub fn keyword_trends<'a>(postings: impl IntoIterator<Item = &'a PageData>, resume_data: &ResumeData) -> Vec<KeywordTrend> {
+    let mut trends = FxHashMap::<String, (usize, f32, Vec<KeywordSection>)>::default();
+    for page_data in postings {
+        for keyword in &page_data.keywords {
+            let (postings, total_score, sections) = trends.entry(keyword.key.to_lowercase()).or_default();
+            *postings += 1;
+            *total_score += keyword.data.score;
+            sections.extend(keyword.data.sources.iter().map(|x| x.section));
+        }
+    }
+
+    let resume_text = resume_text(resume_data);
+    let mut trends: Vec<_> = trends
+        .into_iter()
+        .map(|(keyword, (postings, total_score, mut sections))| {
+            sections.sort();
+            sections.dedup();
+            KeywordTrend { covered: resume_text.contains(&keyword), keyword, postings, total_score, sections }
+        })
+        .collect();
+    trends.sort_by(|a, b| b.postings.cmp(&a.postings).then(b.total_score.total_cmp(&a.total_score)).then_with(|| a.keyword.cmp(&b.keyword)));
+    trends
+}
+
+
+/// Writes the given trends as CSV, ready to be charted in a spreadsheet
+pub fn trends_csv(trends: &[KeywordTrend]) -> String {
+    let mut out = String::new();
+    write_csv_row(&mut out, &["rank", "keyword", "postings", "total_score", "covered", "sections"].map(ToString::to_string));
+    for (i, trend) in trends.iter().enumerate() {
+        let sections = trend.sections.iter().map(|x| x.name()).collect::<Vec<_>>().join(";");
+        write_csv_row(&mut out, &[(i + 1).to_string(), trend.keyword.clone(), trend.postings.to_string(), format!("{:.3}", trend.total_score), trend.covered.to_string(), sections]);
+    }
+    out
+}
+
+
+/// What the postings in one group pay, per year
+pub struct SalarySummary {
+    pub group: String,
+    /// How many postings in this group have a pay range
+    pub postings: usize,
+    /// The lowest pay of any range
+    pub min: f64,
+    /// The median of the middle of each range
+    pub median: f64,
+    /// The highest pay of any range
+    pub max: f64
+}
+
+
+/// Groups postings by the words of their job titles, leaving out short words like "of"
+pub fn title_word_groups(page_data: &PageData) -> Vec<String> {
+    let mut words: Vec<_> = title_words(&page_data.job_title).into_iter().filter(|x| x.len() > 2).collect();
+    words.sort();
+    words
+}
+
+
+/// Groups postings by their location
+pub fn location_groups(page_data: &PageData) -> Vec<String> {
+    vec![page_data.location.clone().unwrap_or_else(|| "Unknown".into())]
+}
+
+
+/// Summarizes the pay of the postings with a pay range in each of the groups given by `groups`,
+/// with the groups with the most postings first
+pub fn salary_summaries<'a>(postings: impl IntoIterator<Item = &'a PageData>, groups: fn(&PageData) -> Vec<String>) -> Vec<SalarySummary> {
+    let mut ranges = FxHashMap::<String, Vec<SalaryRange>>::default();
+    for page_data in postings {
+        let Some(range) = page_data.salary.as_deref().and_then(SalaryRange::parse) else { continue };
+        for group in groups(page_data) {
+            ranges.entry(group).or_default().push(range);
+        }
+    }
+
+    let mut summaries: Vec<_> = ranges
+        .into_iter()
+        .map(|(group, ranges)| {
+            let mut midpoints: Vec<_> = ranges.iter().map(SalaryRange::midpoint).collect();
+            midpoints.sort_by(f64::total_cmp);
+            let middle = midpoints.len() / 2;
+            let median = if midpoints.len() % 2 == 0 {
+                (midpoints[middle - 1] + midpoints[middle]) / 2.0
+            } else {
+                midpoints[middle]
+            };
+            SalarySummary {
+                group,
+                postings: ranges.len(),
+                min: ranges.iter().map(|x| x.min).fold(f64::INFINITY, f64::min),
+                median,
+                max: ranges.iter().map(|x| x.max).fold(0.0, f64::max)
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.postings.cmp(&a.postings).then(b.median.total_cmp(&a.median)).then_with(|| a.group.cmp(&b.group)));
+    summaries
+}
+
+
+/// Writes the given summaries as CSV
+pub fn salaries_csv(summaries: &[SalarySummary]) -> String {
+    let mut out = String::new();
+    write_csv_row(&mut out, &["group", "postings", "min", "median", "max"].map(ToString::to_string));
+    for summary in summaries {
+        write_csv_row(&mut out, &[summary.group.clone(), summary.postings.to_string(), format!("{:.0}", summary.min), format!("{:.0}", summary.median), format!("{:.0}", summary.max)]);
+    }
+    out
+}
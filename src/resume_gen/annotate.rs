@@ -0,0 +1,84 @@
+//! A copy of a job's description with its keywords highlighted, to check what was extracted at a glance
+use std::path::Path;
+
+use anyhow::Context;
+use fxhash::FxHashMap;
+use regex::{Captures, RegexBuilder};
+
+use crate::page_scrapers::PageData;
+
+use super::{selection::{chosen_text, resume_text, Selection}, escape_html, ResumeData};
+
+/// Written next to each resume if `annotate_postings` is enabled
+pub const ANNOTATED_POSTING_FILE_NAME: &str = "posting_annotated.html";
+
+const ANNOTATED_POSTING_STYLE: &str = "
+    body { font-family: sans-serif; max-width: 50rem; margin: 2rem auto; line-height: 1.5; }
+    mark { border-radius: 0.2rem; padding: 0 0.1rem; }
+    .on-resume { background: #b7e4c7; }
+    .left-out { background: #ffe08a; }
+    .missing { background: #f4a6a6; }
+";
+
+
+/// The description of the given job as HTML, with each keyword highlighted by whether it is on the resume,
+/// only in the resume data, or nowhere
+fn annotated_posting(page_data: &PageData, resume_data: &ResumeData, selection: &Selection) -> anyhow::Result<String> {
+    let on_resume = chosen_text(resume_data, selection);
+    let in_resume_data = resume_text(resume_data);
+    // By their lowercased HTML, as that is what is highlighted
+    let keywords: FxHashMap<String, (&'static str, f32)> = page_data.keywords
+        .iter()
+        .map(|k| {
+            let key = k.key.to_lowercase();
+            let class = if on_resume.contains(&key) {
+                "on-resume"
+            } else if in_resume_data.contains(&key) {
+                "left-out"
+            } else {
+                "missing"
+            };
+            (escape_html(&key), (class, k.data.score))
+        })
+        .collect();
+
+    let mut html = format!("<!doctype html>\n<meta charset=\"utf-8\">\n<title>{0} at {1}</title>\n<style>{ANNOTATED_POSTING_STYLE}</style>\n<h1>{0} at {1}</h1>\n",
+        escape_html(&page_data.job_title), escape_html(&page_data.company));
+    html += &format!("<p><a href=\"{0}\">{0}</a></p>\n", escape_html(page_data.url.as_str()));
+    html += "<p><mark class=\"on-resume\">On the resume</mark> <mark class=\"left-out\">In your resume data, but left out</mark> <mark class=\"missing\">Not in your resume data</mark></p>\n<hr>\n";
+    if page_data.description.is_empty() {
+        html += "<p>No description was scraped. Try scraping the website again with --refresh-url</p>\n";
+        return Ok(html)
+    }
+
+    // Longer keywords first, so that they are highlighted instead of the shorter keywords inside them
+    let mut patterns: Vec<_> = keywords.keys().map(|x| regex::escape(x)).collect();
+    patterns.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    let keyword_regex = (!patterns.is_empty())
+        .then(|| RegexBuilder::new(&patterns.join("|")).case_insensitive(true).build())
+        .transpose()
+        .context("Failed to build a pattern out of the keywords")?;
+    for line in page_data.description.lines() {
+        let line = escape_html(line);
+        let line = match &keyword_regex {
+            Some(keyword_regex) => keyword_regex.replace_all(&line, |c: &Captures| {
+                let matched = &c[0];
+                match keywords.get(&matched.to_lowercase()) {
+                    Some((class, score)) => format!("<mark class=\"{class}\" title=\"{score:.3}\">{matched}</mark>"),
+                    None => matched.to_string()
+                }
+            }).into_owned(),
+            None => line
+        };
+        html += &format!("<p>{line}</p>\n");
+    }
+    Ok(html)
+}
+
+
+/// Writes `ANNOTATED_POSTING_FILE_NAME` into the given folder
+pub async fn write_annotated_posting(folder_path: &Path, page_data: &PageData, resume_data: &ResumeData, selection: &Selection) -> anyhow::Result<()> {
+    let path = folder_path.join(ANNOTATED_POSTING_FILE_NAME);
+    let html = annotated_posting(page_data, resume_data, selection)?;
+    tokio::fs::write(&path, html).await.context(format!("Failed to write {path:?}. Do we have permissions?"))
+}
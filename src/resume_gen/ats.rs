@@ -0,0 +1,81 @@
+//! Checks for things in resumes that applicant tracking systems (ATS) have trouble reading
+use headless_chrome::Tab;
+use regex::Regex;
+
+use super::ResumeData;
+
+
+/// Fonts that are installed nearly everywhere, plus the generic CSS families
+const COMMON_FONTS: [&str; 22] = [
+    "arial", "helvetica", "helvetica neue", "calibri", "cambria", "georgia", "garamond", "times new roman",
+    "times", "verdana", "tahoma", "trebuchet ms", "segoe ui", "roboto", "open sans", "lato", "serif",
+    "sans-serif", "system-ui", "inherit", "initial", "unset"
+];
+
+
+/// Warns about constructs in the given template that ATS parsers often choke on
+pub fn lint_template(template: &str) -> Vec<String> {
+    let mut warnings = vec![];
+    let checks = [
+        (r"(?i)<table", "The template uses tables, which ATS often read out of order"),
+        (r"(?i)float\s*:\s*(left|right)", "The template floats elements side by side, which ATS may read out of order"),
+        (r"(?i)(column-count|columns\s*:\s*\d|grid-template-columns)", "The template uses multiple columns, which ATS may read out of order"),
+        (r"(?i)(<img|<svg|<canvas|background-image)", "The template uses images, and ATS cannot read any text in them"),
+        (r"(?i)@font-face", "The template loads its own fonts, which ATS may not be able to read")
+    ];
+    for (pattern, warning) in checks {
+        if Regex::new(pattern).unwrap().is_match(template) {
+            warnings.push(warning.to_string());
+        }
+    }
+
+    let font_family_regex = Regex::new(r"(?i)font-family\s*:([^;}]*)").unwrap();
+    let mut unusual_fonts: Vec<String> = font_family_regex
+        .captures_iter(template)
+        .flat_map(|c| c[1].split(',').map(|x| x.trim().trim_matches(|c| c == '"' || c == '\'').to_string()).collect::<Vec<_>>())
+        .filter(|font| !font.is_empty() && !COMMON_FONTS.contains(&font.to_lowercase().as_str()))
+        .collect();
+    unusual_fonts.sort();
+    unusual_fonts.dedup();
+    for font in unusual_fonts {
+        warnings.push(format!("The template uses the font {font:?}, which ATS may not recognize"));
+    }
+
+    warnings
+}
+
+
+/// Warns about contact info that is missing from the selectable text of the resume in the given tab
+///
+/// Contact info that is only in a link or an image cannot be copied out by an ATS.
+pub(super) fn lint_output(tab: &Tab, resume_data: &ResumeData) -> anyhow::Result<Vec<String>> {
+    let text = tab.evaluate("document.body.innerText", false)?
+        .value
+        .and_then(|x| x.as_str().map(ToString::to_string))
+        .unwrap_or_default();
+    let lowercase_text = text.to_lowercase();
+    let digits = |x: &str| x.chars().filter(char::is_ascii_digit).collect::<String>();
+
+    let mut missing = vec![];
+    if !lowercase_text.contains(&resume_data.name.to_lowercase()) {
+        missing.push("name");
+    }
+    if !lowercase_text.contains(&resume_data.email.to_lowercase()) {
+        missing.push("email");
+    }
+    // Phone numbers may be formatted differently in the template
+    if !digits(&text).contains(&digits(&resume_data.phone_number)) {
+        missing.push("phone number");
+    }
+    if let Some(website) = &resume_data.website {
+        let website = website.to_lowercase();
+        let website = website.trim_start_matches("https://").trim_start_matches("http://").trim_end_matches('/');
+        if !lowercase_text.contains(website) {
+            missing.push("website");
+        }
+    }
+
+    // Links only show their label, which is the most likely reason
+    let hint = if resume_data.plain_text_contact { "" } else { ". Set plain_text_contact = true in resume_data to write it out instead of linking to it" };
+    Ok(missing.into_iter().map(|x| format!("Your {x} is not in the resume as selectable text{hint}")).collect())
+}
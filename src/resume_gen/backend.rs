@@ -0,0 +1,101 @@
+//! Printing resumes into PDFs without Chrome, for when it cannot run
+use std::{io::Write, path::Path, process::{Command, Stdio}, sync::Arc};
+
+use anyhow::Context;
+use headless_chrome::Tab;
+use serde::Deserialize;
+
+use super::paper::Paper;
+
+
+/// What resumes are printed into PDFs with
+#[derive(Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderBackend {
+    /// Supports all CSS, and is the only backend that fits resumes onto one page and checks what an ATS can read
+    #[default]
+    Chrome,
+    /// Only supports older CSS, so templates that use grid (such as `"sidebar"`) are laid out differently
+    Wkhtmltopdf,
+    /// Supports most CSS for print, but not JavaScript
+    Weasyprint
+}
+
+
+/// How a single resume is printed
+pub enum Renderer {
+    Chrome(Arc<Tab>),
+    /// A program that prints HTML, along with its executable if it is not on the PATH
+    Program(RenderBackend, Option<Arc<Path>>)
+}
+
+
+impl RenderBackend {
+    /// The name of the program's executable
+    fn executable(self) -> &'static str {
+        match self {
+            Self::Chrome => "chrome",
+            Self::Wkhtmltopdf => "wkhtmltopdf",
+            Self::Weasyprint => "weasyprint"
+        }
+    }
+
+    /// How a resume is printed with this backend, given a tab if this is `Chrome`
+    pub fn renderer(self, tab: Option<Arc<Tab>>, program: Option<Arc<Path>>) -> Renderer {
+        match tab {
+            Some(tab) if self == Self::Chrome => Renderer::Chrome(tab),
+            _ => Renderer::Program(self, program)
+        }
+    }
+}
+
+
+impl Renderer {
+    pub fn backend(&self) -> RenderBackend {
+        match self {
+            Self::Chrome(_) => RenderBackend::Chrome,
+            Self::Program(backend, _) => *backend
+        }
+    }
+}
+
+
+/// Prints the given HTML onto the given paper with the given program, as is
+///
+/// The HTML is given through stdin and the PDF is read from stdout, so nothing is written to disk.
+pub(super) fn print_with_program(backend: RenderBackend, program: Option<&Path>, html: &str, paper: Paper) -> anyhow::Result<Vec<u8>> {
+    let executable = program.unwrap_or_else(|| Path::new(backend.executable()));
+    let html = format!("{html}\n<style>{}</style>", paper.page_css());
+    let mut command = Command::new(executable);
+    match backend {
+        RenderBackend::Chrome => return Err(anyhow::anyhow!("Chrome cannot print without a tab")),
+        RenderBackend::Wkhtmltopdf => {
+            let (width, height, margin) = paper.dimensions();
+            command
+                .args(["--quiet", "--encoding", "utf-8"])
+                .args(["--page-width", &format!("{width}in"), "--page-height", &format!("{height}in")])
+                .args(["-T", &format!("{margin}in"), "-B", &format!("{margin}in"), "-L", &format!("{margin}in"), "-R", &format!("{margin}in")])
+                .args(["-", "-"]);
+        }
+        RenderBackend::Weasyprint => {
+            command.args(["--encoding", "utf-8", "-", "-"]);
+        }
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(format!("Failed to start {executable:?}. Is {} installed?", backend.executable()))?;
+
+    // Written on another thread, so that the program does not get stuck writing a PDF that is not being read
+    let mut stdin = child.stdin.take().context("The program's stdin should have been piped")?;
+    let writer = std::thread::spawn(move || stdin.write_all(html.as_bytes()));
+    let output = child.wait_with_output().context(format!("Failed to wait for {executable:?}"))?;
+    writer.join().map_err(|_| anyhow::anyhow!("Failed to give the resume to {executable:?}"))?.context(format!("Failed to give the resume to {executable:?}"))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(anyhow::anyhow!("{executable:?} failed ({}): {}", output.status, String::from_utf8_lossy(&output.stderr).trim()))
+    }
+    Ok(output.stdout)
+}
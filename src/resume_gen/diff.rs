@@ -0,0 +1,148 @@
+//! What tailoring changed between two generated resumes, going by what was chosen for each rather than their PDFs
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use super::{layout::FLAT_JOBS_FOLDER_NAME, ResumeData, JOB_METADATA_FILE_NAME};
+
+
+/// Given instead of a resume folder to compare against the resume data with nothing left out.
+/// Also the folder in the output directory that the resume with every note is rendered into
+pub const BASE_RESUME: &str = "base";
+
+
+/// The parts of `JOB_METADATA_FILE_NAME` that are compared
+#[derive(Deserialize)]
+struct SavedMetadata {
+    company: String,
+    job_title: String,
+    match_score: f32,
+    top_keywords: Vec<String>,
+    education: Vec<SavedEducation>,
+    /// Not written by older versions
+    #[serde(default)]
+    experience: Vec<SavedExperience>
+}
+
+
+#[derive(Deserialize)]
+struct SavedEducation {
+    school_name: String,
+    notes: Vec<String>
+}
+
+
+#[derive(Deserialize)]
+struct SavedExperience {
+    employer: String,
+    title: String,
+    bullets: Vec<String>
+}
+
+
+fn experience_name(title: &str, employer: &str) -> String {
+    format!("{title} at {employer}")
+}
+
+
+/// What was put on one resume
+pub struct ResumeContents {
+    /// The company and job title, or `BASE_RESUME`
+    pub label: String,
+    pub match_score: Option<f32>,
+    /// The name of each education and experience entry, and the notes or bullets that were put under it
+    pub entries: Vec<(String, Vec<String>)>,
+    pub top_keywords: Vec<String>
+}
+
+
+impl ResumeContents {
+    /// The resume data with every note, as it would be without tailoring
+    pub fn base(resume_data: &ResumeData) -> Self {
+        Self {
+            label: BASE_RESUME.to_string(),
+            match_score: None,
+            entries: resume_data.education
+                .iter()
+                .map(|x| (x.school_name.clone(), x.notes.clone()))
+                .chain(resume_data.experience.iter().map(|x| (experience_name(&x.title, &x.employer), x.bullets.clone())))
+                .collect(),
+            top_keywords: vec![]
+        }
+    }
+
+    /// Reads what was put on the resume in the given folder, which can also be the name of a folder in `output_dir`
+    /// or the name of a resume written with `OutputLayout::Flat`
+    pub fn load(output_dir: &Path, folder: &str) -> anyhow::Result<Self> {
+        let flat_folder = output_dir.join(FLAT_JOBS_FOLDER_NAME).join(folder.trim_end_matches(".pdf"));
+        let folder = if Path::new(folder).is_dir() {
+            Path::new(folder).to_path_buf()
+        } else if flat_folder.is_dir() {
+            flat_folder
+        } else {
+            output_dir.join(folder)
+        };
+        let path = folder.join(JOB_METADATA_FILE_NAME);
+        let metadata = std::fs::read_to_string(&path).context(format!("Failed to read {path:?}. Has a resume been generated there?"))?;
+        let metadata: SavedMetadata = toml::from_str(&metadata).context(format!("Failed to parse {path:?}. It may be from an older version, which is fixed by generating the resume again"))?;
+        Ok(Self {
+            label: format!("{} {}", metadata.company, metadata.job_title),
+            match_score: Some(metadata.match_score),
+            entries: metadata.education
+                .into_iter()
+                .map(|x| (x.school_name, x.notes))
+                .chain(metadata.experience.into_iter().map(|x| (experience_name(&x.title, &x.employer), x.bullets)))
+                .collect(),
+            top_keywords: metadata.top_keywords
+        })
+    }
+
+    fn describe(&self) -> String {
+        match self.match_score {
+            Some(match_score) => format!("{} (match score {:.1}%)", self.label, match_score * 100.0),
+            None => self.label.clone()
+        }
+    }
+}
+
+
+/// Prints what is only on `a` (with a `-`), what is only on `b` (with a `+`), and what is on both
+pub fn print_diff(a: &ResumeContents, b: &ResumeContents) {
+    let mut out = format!("- {}\n+ {}\n", a.describe(), b.describe());
+
+    let mut names: Vec<&str> = a.entries.iter().map(|(x, _)| x.as_str()).collect();
+    names.extend(b.entries.iter().map(|(x, _)| x.as_str()).filter(|x| !a.entries.iter().any(|(y, _)| y == x)));
+    for name in names {
+        let a_notes = a.entries.iter().find(|(x, _)| x == name).map(|(_, x)| x.as_slice());
+        let b_notes = b.entries.iter().find(|(x, _)| x == name).map(|(_, x)| x.as_slice());
+        match (a_notes, b_notes) {
+            (Some(_), None) => out += &format!("\n- {name}\n"),
+            (None, Some(_)) => out += &format!("\n+ {name}\n"),
+            _ => out += &format!("\n  {name}\n")
+        }
+
+        let (a_notes, b_notes) = (a_notes.unwrap_or_default(), b_notes.unwrap_or_default());
+        for note in a_notes {
+            let marker = if b_notes.contains(note) { ' ' } else { '-' };
+            out += &format!("{marker}       {note}\n");
+        }
+        for note in b_notes.iter().filter(|x| !a_notes.contains(x)) {
+            out += &format!("+       {note}\n");
+        }
+    }
+
+    let only_a: Vec<_> = a.top_keywords.iter().filter(|x| !b.top_keywords.contains(x)).map(String::as_str).collect();
+    let only_b: Vec<_> = b.top_keywords.iter().filter(|x| !a.top_keywords.contains(x)).map(String::as_str).collect();
+    if !only_a.is_empty() || !only_b.is_empty() {
+        out += "\n  Top keywords\n";
+        if !only_a.is_empty() {
+            out += &format!("-       {}\n", only_a.join(", "));
+        }
+        if !only_b.is_empty() {
+            out += &format!("+       {}\n", only_b.join(", "));
+        }
+    }
+
+    print!("{out}");
+}
@@ -0,0 +1,68 @@
+//! Why each note and bullet was put on a resume, so that the tailoring can be checked and corrected
+use std::{fmt::Write, path::Path};
+
+use anyhow::Context;
+
+use crate::page_scrapers::PageData;
+
+use super::{selection::{chosen_text, matched_keywords, Selection}, ResumeData};
+
+/// Written next to each resume, listing the keywords of the job that each note and bullet matched
+pub const EXPLANATION_FILE_NAME: &str = "explanation.md";
+/// How many of the job's most important keywords that nothing on the resume matched are listed
+const UNMATCHED_KEYWORDS: usize = 10;
+
+
+/// Writes the given note or bullet as a list item, with the keywords it matched nested under it
+fn explain_line(out: &mut String, text: &str, page_data: &PageData) {
+    let _ = writeln!(out, "- {text}");
+    let matched = matched_keywords(text, page_data);
+    if matched.is_empty() {
+        let _ = writeln!(out, "    - *No keywords matched*");
+    }
+    for (keyword, score) in matched {
+        let _ = writeln!(out, "    - {keyword} ({score:.3})");
+    }
+}
+
+
+/// The contents of `EXPLANATION_FILE_NAME` for the given job
+fn explanation(page_data: &PageData, resume_data: &ResumeData, selection: &Selection) -> String {
+    let mut out = format!("# {} at {}\n\n", page_data.job_title, page_data.company);
+    let _ = writeln!(out, "Match score: {:.1}%\n", selection.match_score * 100.0);
+    let _ = writeln!(out, "Each note and bullet is listed with the keywords of the job that it matched, and their scores.\n");
+
+    for (education, notes) in selection.education(resume_data) {
+        let _ = writeln!(out, "## {}\n", education.school_name);
+        if notes.is_empty() {
+            let _ = writeln!(out, "No notes were chosen\n");
+        }
+        for &i in notes {
+            explain_line(&mut out, &education.notes[i], page_data);
+        }
+        out.push('\n');
+    }
+    for (experience, bullets) in selection.experience(resume_data) {
+        let _ = writeln!(out, "## {} at {}\n", experience.title, experience.employer);
+        for &i in bullets {
+            explain_line(&mut out, &experience.bullets[i], page_data);
+        }
+        out.push('\n');
+    }
+
+    let matched: Vec<_> = matched_keywords(&chosen_text(resume_data, selection), page_data).into_iter().map(|(keyword, _)| keyword).collect();
+    let mut unmatched: Vec<_> = page_data.keywords.iter().filter(|k| !matched.contains(&k.key.as_str())).collect();
+    unmatched.sort_by(|a, b| b.data.score.total_cmp(&a.data.score).then_with(|| a.key.cmp(&b.key)));
+    let _ = writeln!(out, "## Keywords that nothing on the resume matched\n");
+    for k in unmatched.into_iter().take(UNMATCHED_KEYWORDS) {
+        let _ = writeln!(out, "- {} ({:.3})", k.key, k.data.score);
+    }
+    out
+}
+
+
+/// Writes `EXPLANATION_FILE_NAME` into the given folder
+pub(super) async fn write_explanation(folder_path: &Path, page_data: &PageData, resume_data: &ResumeData, selection: &Selection) -> anyhow::Result<()> {
+    let path = folder_path.join(EXPLANATION_FILE_NAME);
+    tokio::fs::write(&path, explanation(page_data, resume_data, selection)).await.context(format!("Failed to write {path:?}. Do we have permissions?"))
+}
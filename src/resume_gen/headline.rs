@@ -0,0 +1,45 @@
+//! A headline under your name that mirrors the title of the job, such as "Software Engineer — Backend"
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::dedupe::{strip_brackets, title_words};
+
+
+/// When the title of a job can be used as the headline of its resume
+///
+/// The title is only used if it is compatible with one of `titles`, so that the headline never
+/// says you are something you are not.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Headline {
+    /// Job titles that describe you, such as "Software Engineer". A job's title is compatible if it
+    /// has every word of one of these, such as "Senior Software Engineer (Backend)"
+    titles: Vec<String>,
+    /// Headlines to use instead of some job titles, such as `{ "Software Engineer II" = "Software Engineer" }`.
+    /// These are used even if the job's title is not compatible with `titles`
+    #[serde(default)]
+    rename: FxHashMap<String, String>,
+    /// The headline of jobs whose title is not compatible. There is no headline for them by default
+    fallback: Option<String>
+}
+
+
+impl Headline {
+    /// The headline of the resume for a job with the given title, if any
+    pub fn for_job(&self, job_title: &str) -> Option<String> {
+        let job_title = strip_brackets(job_title).split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some((_, headline)) = self.rename.iter().find(|(title, _)| title.eq_ignore_ascii_case(&job_title)) {
+            return Some(headline.clone())
+        }
+
+        let job_words = title_words(&job_title);
+        let compatible = self.titles
+            .iter()
+            .map(|x| title_words(x))
+            .any(|words| !words.is_empty() && words.is_subset(&job_words));
+        if compatible && !job_title.is_empty() {
+            Some(job_title)
+        } else {
+            self.fallback.clone()
+        }
+    }
+}
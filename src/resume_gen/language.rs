@@ -0,0 +1,167 @@
+//! Resumes in languages other than English, for applying to jobs in other countries
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::ResumeData;
+
+const TRANSLATIONS: &str = include_str!("translations.toml");
+/// The language resumes are in if none is given
+pub const DEFAULT_LANGUAGE: &str = "en";
+/// The most texts translated in one request, as DeepL rejects requests with more than 50
+const MAX_BATCH_TEXTS: usize = 50;
+/// The most bytes of text translated in one request, leaving room under DeepL's limit of 128 KiB for the rest of it
+const MAX_BATCH_BYTES: usize = 100 * 1024;
+
+
+/// The text that templates put around the resume data, in one language
+#[derive(Deserialize, Serialize)]
+pub struct Translation {
+    /// The names of the months, starting from January
+    months: [String; 12],
+    /// How a month of a year is written, with `{month}` and `{year}` replaced
+    date_format: String,
+    /// What `<label-...>` placeholders are replaced with, by the name after `label-`
+    labels: BTreeMap<String, String>
+}
+
+
+fn translations() -> &'static BTreeMap<String, Translation> {
+    static TRANSLATIONS_CELL: OnceLock<BTreeMap<String, Translation>> = OnceLock::new();
+    TRANSLATIONS_CELL.get_or_init(|| toml::from_str(TRANSLATIONS).expect("The bundled translations should be valid"))
+}
+
+
+impl Translation {
+    /// The bundled translation of the given language, such as "es"
+    pub fn get(language: &str) -> anyhow::Result<&'static Self> {
+        translations()
+            .get(&language.to_lowercase())
+            .with_context(|| format!("There is no translation for the language {language:?}. Available languages are {}", translations().keys().cloned().collect::<Vec<_>>().join(", ")))
+    }
+
+    /// The translated label with the given name, or the name itself if there is no such label
+    pub fn label<'a>(&'a self, name: &'a str) -> &'a str {
+        self.labels.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    pub fn format_date(&self, year: u16, month: u8) -> String {
+        self.date_format
+            .replace("{month}", &self.months[month.clamp(1, 12) as usize - 1])
+            .replace("{year}", &year.to_string())
+    }
+}
+
+
+/// A service that translates the resume data itself, picked with `backend`
+#[derive(Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum TranslationBackend {
+    Deepl {
+        api_key: String
+    },
+    Libretranslate {
+        /// Where LibreTranslate is hosted, such as "https://libretranslate.com"
+        url: Url,
+        api_key: Option<String>
+    }
+}
+
+
+impl TranslationBackend {
+    /// Translates each of the given texts into the given language, in the same order
+    ///
+    /// Texts are sent in batches of at most `MAX_BATCH_TEXTS` texts and `MAX_BATCH_BYTES` bytes, so that long resumes
+    /// are not rejected.
+    async fn translate(&self, client: &reqwest::Client, texts: Vec<String>, language: &str) -> anyhow::Result<Vec<String>> {
+        let mut translated = Vec::with_capacity(texts.len());
+        let mut start = 0;
+        while start < texts.len() {
+            let mut end = start;
+            let mut bytes = 0;
+            // A text that is too long on its own is still sent, in a batch by itself
+            while end < texts.len() && end - start < MAX_BATCH_TEXTS && (end == start || bytes + texts[end].len() <= MAX_BATCH_BYTES) {
+                bytes += texts[end].len();
+                end += 1;
+            }
+            let batch = self.translate_batch(client, &texts[start..end], language).await?;
+            if batch.len() != end - start {
+                return Err(anyhow::anyhow!("{} texts were translated, but {} were sent", batch.len(), end - start))
+            }
+            translated.extend(batch);
+            start = end;
+        }
+        Ok(translated)
+    }
+
+    /// Translates the given texts in one request
+    async fn translate_batch(&self, client: &reqwest::Client, texts: &[String], language: &str) -> anyhow::Result<Vec<String>> {
+        match self {
+            Self::Deepl { api_key } => {
+                // Keys of the free API end in ":fx", and only work with the free API
+                let host = if api_key.ends_with(":fx") { "api-free.deepl.com" } else { "api.deepl.com" };
+                let response: serde_json::Value = client
+                    .post(format!("https://{host}/v2/translate"))
+                    .header(reqwest::header::AUTHORIZATION, format!("DeepL-Auth-Key {api_key}"))
+                    .json(&serde_json::json!({ "text": texts, "target_lang": language.to_uppercase() }))
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .context("Failed to translate with DeepL. Is the API key correct?")?
+                    .json()
+                    .await?;
+                response["translations"]
+                    .as_array()
+                    .context("DeepL sent an invalid response")?
+                    .iter()
+                    .map(|x| x["text"].as_str().map(ToString::to_string).context("DeepL sent an invalid response"))
+                    .collect()
+            }
+            Self::Libretranslate { url, api_key } => {
+                let response: serde_json::Value = client
+                    .post(url.join("translate")?)
+                    .json(&serde_json::json!({ "q": texts, "source": "auto", "target": language, "format": "text", "api_key": api_key }))
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .context(format!("Failed to translate with LibreTranslate at {url}"))?
+                    .json()
+                    .await?;
+                response["translatedText"]
+                    .as_array()
+                    .context("LibreTranslate sent an invalid response")?
+                    .iter()
+                    .map(|x| x.as_str().map(ToString::to_string).context("LibreTranslate sent an invalid response"))
+                    .collect()
+            }
+        }
+    }
+
+    /// Translates the majors, notes, job titles and bullets of the resume data into the given language
+    ///
+    /// Names, such as of schools, are left as they are.
+    pub async fn translate_resume_data(&self, client: &reqwest::Client, resume_data: &ResumeData, language: &str) -> anyhow::Result<ResumeData> {
+        let texts: Vec<String> = resume_data.education
+            .iter()
+            .flat_map(|education| std::iter::once(&education.major).chain(&education.notes))
+            .chain(resume_data.experience.iter().flat_map(|experience| std::iter::once(&experience.title).chain(&experience.bullets)))
+            .cloned()
+            .collect();
+        let mut translated = self.translate(client, texts, language).await?.into_iter();
+
+        let mut resume_data = resume_data.clone();
+        for education in &mut resume_data.education {
+            for text in std::iter::once(&mut education.major).chain(&mut education.notes) {
+                *text = translated.next().context("Fewer texts were translated than were sent")?;
+            }
+        }
+        for experience in &mut resume_data.experience {
+            for text in std::iter::once(&mut experience.title).chain(&mut experience.bullets) {
+                *text = translated.next().context("Fewer texts were translated than were sent")?;
+            }
+        }
+        Ok(resume_data)
+    }
+}
@@ -0,0 +1,86 @@
+//! Where in the output directory each resume is written
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::page_scrapers::PageData;
+
+use super::{resume_folder_name, unix_to_datetime};
+
+
+/// The folder inside the output directory that the files of each job are kept in with `OutputLayout::Flat`,
+/// so that only resumes are left in the output directory itself
+pub const FLAT_JOBS_FOLDER_NAME: &str = ".jobs";
+
+
+/// How resumes are organized in the output directory
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputLayout {
+    /// `{company} {title}/resume.pdf`
+    #[default]
+    Folders,
+    /// `{company} {title}.pdf`, with the rest of the files of each job in `FLAT_JOBS_FOLDER_NAME`
+    Flat,
+    /// `{company}/{title}/resume.pdf`
+    ByCompany,
+    /// `2024-06-01/{company} {title}/resume.pdf`, by the date the job was first scraped
+    ByDate
+}
+
+
+/// Where a resume is written, along with the folder that the rest of the files of its job are kept in
+#[derive(Clone)]
+pub struct ResumePaths {
+    pub folder: PathBuf,
+    pub resume: PathBuf
+}
+
+
+/// The given name with its path separators replaced, so that it is a single folder or file name
+///
+/// Scraped company names and job titles such as "Engineer I/II" would otherwise be split into several folders.
+fn path_component(name: &str) -> String {
+    name.replace(['/', '\\'], "-")
+}
+
+
+impl OutputLayout {
+    /// Where the resume for the given job is written in `output_dir`
+    ///
+    /// `output_name` is used instead of the company and job title if given.
+    pub fn paths(self, output_dir: &Path, page_data: &PageData, output_name: Option<&str>) -> ResumePaths {
+        let name = path_component(&output_name.map(ToString::to_string).unwrap_or_else(|| resume_folder_name(page_data)));
+        let folder = match self {
+            Self::Folders => output_dir.join(&name),
+            Self::Flat => {
+                return ResumePaths {
+                    folder: output_dir.join(FLAT_JOBS_FOLDER_NAME).join(&name),
+                    resume: output_dir.join(format!("{name}.pdf"))
+                }
+            }
+            Self::ByCompany if output_name.is_some() => output_dir.join(&name),
+            Self::ByCompany => output_dir.join(path_component(&page_data.company)).join(path_component(&page_data.job_title)),
+            Self::ByDate => {
+                let date = unix_to_datetime(page_data.scraped_at).date.expect("Datetime should have had a date");
+                output_dir.join(date.to_string()).join(&name)
+            }
+        };
+        ResumePaths { resume: folder.join("resume.pdf"), folder }
+    }
+}
+
+
+impl ResumePaths {
+    /// Where another version of this resume with the given name is written, such as a variant
+    ///
+    /// It is put in a folder inside this one, or next to this resume if it is not in this folder.
+    pub fn nested(&self, name: &str) -> Self {
+        let folder = self.folder.join(name);
+        if self.resume.parent() == Some(self.folder.as_path()) {
+            return Self { resume: folder.join("resume.pdf"), folder }
+        }
+        let stem = self.resume.file_stem().unwrap_or_default().to_string_lossy();
+        Self { resume: self.resume.with_file_name(format!("{stem} {name}.pdf")), folder }
+    }
+}
@@ -0,0 +1,125 @@
+//! Spelling and grammar checks of the resume data, using LanguageTool
+use anyhow::Context;
+use serde::Deserialize;
+use url::Url;
+
+use super::ResumeData;
+
+/// The public LanguageTool server, which is rate limited
+pub const DEFAULT_LANGUAGETOOL_URL: &str = "https://api.languagetool.org/v2/";
+/// Put between fields, so that LanguageTool does not treat them as one sentence
+const FIELD_SEPARATOR: &str = "\n\n";
+
+
+/// A possible typo or grammar mistake in the resume data
+pub struct Issue {
+    /// Where the mistake is in config.toml, such as `resume_data.education[0].notes[2]`
+    pub field: String,
+    /// The text that has the mistake
+    pub text: String,
+    pub message: String,
+    /// What LanguageTool suggests replacing `text` with, best first
+    pub replacements: Vec<String>
+}
+
+
+#[derive(Deserialize)]
+struct CheckResponse {
+    matches: Vec<CheckMatch>
+}
+
+
+#[derive(Deserialize)]
+struct CheckMatch {
+    message: String,
+    /// In UTF-16 code units, as LanguageTool is written in Java
+    offset: usize,
+    length: usize,
+    replacements: Vec<CheckReplacement>
+}
+
+
+#[derive(Deserialize)]
+struct CheckReplacement {
+    value: String
+}
+
+
+/// Every field of the resume data that is prose, along with where it is in config.toml
+///
+/// Names and contact info are left out, as they are rarely in a dictionary.
+fn prose_fields(resume_data: &ResumeData) -> Vec<(String, &str)> {
+    let mut fields = vec![];
+    for (i, education) in resume_data.education.iter().enumerate() {
+        fields.push((format!("resume_data.education[{i}].major"), education.major.as_str()));
+        for (j, note) in education.notes.iter().enumerate() {
+            fields.push((format!("resume_data.education[{i}].notes[{j}]"), note.as_str()));
+        }
+    }
+    for (i, experience) in resume_data.experience.iter().enumerate() {
+        fields.push((format!("resume_data.experience[{i}].title"), experience.title.as_str()));
+        for (j, bullet) in experience.bullets.iter().enumerate() {
+            fields.push((format!("resume_data.experience[{i}].bullets[{j}]"), bullet.as_str()));
+        }
+    }
+    fields
+}
+
+
+/// LanguageTool only checks spelling for languages with a region, such as en-US instead of en
+fn languagetool_language(language: &str) -> &str {
+    match language {
+        "en" => "en-US",
+        "de" => "de-DE",
+        "pt" => "pt-PT",
+        language => language
+    }
+}
+
+
+/// Checks the spelling and grammar of the resume data, which is written in the given language
+pub async fn lint_resume_data(client: &reqwest::Client, languagetool_url: &Url, resume_data: &ResumeData, language: &str) -> anyhow::Result<Vec<Issue>> {
+    let fields = prose_fields(resume_data);
+    // Every field is checked in one request, as the public server only allows a few requests a minute
+    let mut text = String::new();
+    let mut starts = vec![];
+    for (_, field) in &fields {
+        if !text.is_empty() {
+            text += FIELD_SEPARATOR;
+        }
+        starts.push(text.encode_utf16().count());
+        text += field;
+    }
+    if text.trim().is_empty() {
+        return Ok(vec![])
+    }
+
+    let url = languagetool_url.join("check")?;
+    let response: CheckResponse = client
+        .post(url.clone())
+        .form(&[("text", text.as_str()), ("language", languagetool_language(language))])
+        .send()
+        .await?
+        .error_for_status()
+        .context(format!("Failed to check the resume data with LanguageTool at {url}"))?
+        .json()
+        .await
+        .context("LanguageTool sent an invalid response")?;
+
+    Ok(response.matches
+        .into_iter()
+        .filter_map(|x| {
+            let i = starts.partition_point(|&start| start <= x.offset).checked_sub(1)?;
+            let (field, field_text) = &fields[i];
+            let field_text: Vec<u16> = field_text.encode_utf16().collect();
+            let start = x.offset - starts[i];
+            let text = String::from_utf16_lossy(field_text.get(start..start + x.length)?);
+            Some(Issue {
+                field: field.clone(),
+                text,
+                message: x.message,
+                replacements: x.replacements.into_iter().map(|x| x.value).collect()
+            })
+        })
+        .collect())
+}
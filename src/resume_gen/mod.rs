@@ -1,22 +1,41 @@
-use std::{sync::Arc, path::PathBuf};
+use std::{sync::Arc, path::{Path, PathBuf}, hash::{Hash, Hasher}, time::{Duration, Instant}};
 
 use anyhow::Context;
-use headless_chrome::{Tab, types::PrintToPdfOptions};
+use fs4::FileExt;
+use fxhash::{FxHashMap, FxHasher};
+use headless_chrome::{Tab, protocol::cdp::{Emulation, Page}};
 use regex::{Regex, Captures};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tokio::fs::DirBuilder;
 use validator::Validate;
 
-use crate::page_scrapers::PageData;
+use crate::{page_scrapers::PageData, timings::Timings};
 
-pub(super) const SMALLEST_FONT_PERCENTAGE: f64 = 0.0003;
-pub(super) const A4_PAGE_HEIGHT_PX: f64 = 973.0;
+use self::{backend::{print_with_program, RenderBackend, Renderer}, explanation::{write_explanation, EXPLANATION_FILE_NAME}, headline::Headline, language::Translation, layout::ResumePaths, paper::{Paper, PrintableArea}, selection::{Selection, SelectionLimits, Strategy}};
+
+pub mod annotate;
+pub mod ats;
+pub mod backend;
+pub mod diff;
+pub mod explanation;
+pub mod headline;
+pub mod language;
+pub mod layout;
+pub mod lint;
+pub mod paper;
+pub mod redact;
+pub mod review;
+pub mod selection;
+pub mod wizard;
+
+/// The smallest font size that can be read, as a fraction of the height of the printable area of the page
+pub const SMALLEST_FONT_PERCENTAGE: f64 = 0.0003;
 const DEFAULT_RESUME_HTML: &str = include_str!("default_template.html");
-const MIN_DEFAULT_RESUME_FONT_SIZE: f64 = 16.0;
+const SIDEBAR_RESUME_HTML: &str = include_str!("sidebar_template.html");
 
 
 /// Standardized information about some form of education, such as college/university.
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Serialize, Validate, Clone)]
 struct Education {
     /// Your final cumulative GPA, or the current cumulative GPA that you have.
     #[validate(range(min = 0))]
@@ -55,13 +74,43 @@ struct Education {
     /// 
     /// Extra-curriculars that cannot stand in their own section can go here. Each
     /// entry will be scanned for keywords and inserted into a resume based on the
-    /// keywords in a job posting.
+    /// keywords in a job posting, most relevant first. The least relevant are left out
+    /// if the resume would not fit on one page otherwise.
     notes: Vec<String>
 }
 
+/// A job that you have worked, such as an internship.
+#[derive(Deserialize, Serialize, Validate, Clone)]
+struct Experience {
+    /// The company or organization that you worked for.
+    employer: String,
+    /// Your job title.
+    title: String,
+    /// Where you worked, such as "San Diego, CA" or "Remote".
+    location: Option<String>,
+    /// The year that you started this job.
+    #[validate(range(min = 1970, max = 2070))]
+    start_year: u16,
+    /// The month of the year that you started this job.
+    #[validate(range(min = 1, max = 12))]
+    start_month: u8,
+    /// The year that this job ended. Leave out if you still work here.
+    #[validate(range(min = 1970, max = 2070))]
+    end_year: Option<u16>,
+    /// The month of the year that this job ended.
+    #[validate(range(min = 1, max = 12))]
+    end_month: Option<u8>,
+    /// What you did and achieved in this job.
+    /// 
+    /// Each bullet is scanned for keywords, and only the bullets most relevant to a job posting
+    /// are put on its resume.
+    #[serde(default)]
+    bullets: Vec<String>
+}
+
 /// Information that the resume builder can use to create a concise and succint resume.
-#[derive(Deserialize, Validate)]
-pub(super) struct ResumeData {
+#[derive(Deserialize, Serialize, Validate, Clone, Default)]
+pub struct ResumeData {
     /// Your full name, as written on a government issued ID.
     name: String,
     /// A phone number that you can be readily contacted on.
@@ -97,33 +146,197 @@ pub(super) struct ResumeData {
     /// through email, phone, or linkedin, instead of by mail. If a company really needed your address,
     /// they may ask you directly towards the end of the recruitment process.
     address: Option<String>,
+    /// Write out your email, website and linkedin as plain text instead of as links, and your phone number
+    /// without a `tel:` link. Some ATS only read the text of a resume, so they never see what a link points to
+    #[serde(default)]
+    plain_text_contact: bool,
+    /// Put the title of each job under your name, when it is one of the titles you give here,
+    /// such as `{ titles = ["Software Engineer"] }`
+    headline: Option<Headline>,
     /// A collection of information regarding schools you've attended.
-    education: Vec<Education>
+    #[validate]
+    education: Vec<Education>,
+    /// Jobs that you have worked, most recent first.
+    #[serde(default)]
+    #[validate]
+    experience: Vec<Experience>
 }
 
-pub(super) const OUTPUT_PATH: &str = "resumes/";
+pub const OUTPUT_PATH: &str = "resumes/";
 
 
 #[derive(Clone)]
-pub(super) enum ResumeTemplate {
+pub enum ResumeTemplate {
     Custom {
-        template: Arc<String>,
-        min_font_size: f64
+        template: Arc<String>
     },
-    Default
+    Default,
+    /// Contact info and education in a narrow column, beside experience
+    Sidebar
 }
 
 
-pub(super) struct Regexes {
+/// Placeholders that every template must have, so that recruiters can contact you,
+/// along with the placeholders that can be used in their place
+const REQUIRED_PLACEHOLDERS: [(&str, &[&str]); 3] = [("<name>", &[]), ("<email>", &["<contact>"]), ("<phonenumber>", &["<contact>"])];
+/// Put between each piece of contact info in `<contact>`
+const CONTACT_SEPARATOR: &str = "<span class=\"separator\"> | </span>";
+/// Characters that phone numbers are written with, besides digits
+const PHONE_NUMBER_PUNCTUATION: [char; 6] = ['+', ' ', '-', '.', '(', ')'];
+/// Sections of templates, along with the placeholders that are only substituted inside their `<entries>`
+const SECTIONS: [(&str, &str, &[&str]); 2] = [
+    ("<education>", "</education>", &["<school-name>", "<major>", "<gpa>", "<max-gpa>", "<start-date>", "<end-date>", "<notes>"]),
+    ("<experience>", "</experience>", &["<employer>", "<job-title>", "<location>", "<dates>", "<start-date>", "<end-date>", "<bullets>"])
+];
+/// Every placeholder with a hyphen, as they could otherwise be mistaken for custom HTML elements
+const HYPHENATED_PLACEHOLDERS: [&str; 5] = ["school-name", "max-gpa", "job-title", "start-date", "end-date"];
+
+
+/// Finds placeholders in the given template that will not be substituted
+///
+/// Fails if any of `REQUIRED_PLACEHOLDERS` are missing, and returns warnings for anything else.
+fn check_placeholders(template: &str, regexes: &Regexes) -> anyhow::Result<Vec<String>> {
+    let missing: Vec<_> = REQUIRED_PLACEHOLDERS
+        .iter()
+        .filter(|(placeholder, alternatives)| !template.contains(placeholder) && !alternatives.iter().any(|x| template.contains(x)))
+        .map(|(placeholder, _)| *placeholder)
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!("The template is missing {}, which recruiters need to contact you", missing.join(", ")))
+    }
+
+    let mut warnings = vec![];
+    // What is left of the template once every section is cut out of it
+    let mut outside = template.to_string();
+    for (open, close, entry_placeholders) in SECTIONS {
+        match (outside.find(open), outside.rfind(close)) {
+            (Some(start), Some(end)) if start < end => {
+                let block = &outside[start..end];
+                if !block.contains("<entries>") || !block.contains("</entries>") {
+                    warnings.push(format!("{open} has no <entries></entries> inside it, so none of its entries will be on the resume"));
+                }
+                outside.replace_range(start..end + close.len(), "");
+            }
+            (None, None) => warnings.push(format!("The template has no {open} section, so it will not be on the resume")),
+            _ => warnings.push(format!("{open} is not closed with {close}, so it will be left in the resume as is"))
+        }
+        for placeholder in entry_placeholders.iter().filter(|x| outside.contains(**x)) {
+            warnings.push(format!("{placeholder} is outside of {open}, so it will be left in the resume as is"));
+        }
+    }
+
+    let mut unknown: Vec<_> = regexes.hyphenated
+        .captures_iter(template)
+        .filter(|c| !regexes.label.is_match(&c[0]) && !regexes.region.is_match(&c[0]))
+        .map(|c| c.get(1).unwrap().as_str())
+        .filter(|x| !HYPHENATED_PLACEHOLDERS.contains(x))
+        .collect();
+    unknown.sort();
+    unknown.dedup();
+    for name in unknown {
+        warnings.push(format!("<{name}> is not a placeholder, so it will be left in the resume as is. Is it misspelled?"));
+    }
+
+    Ok(warnings)
+}
+
+
+impl ResumeTemplate {
+    /// Reads a custom template from the given path, warning about any placeholders that will not be substituted
+    ///
+    /// Font sizes are only checked once the template is rendered, as they can depend on anything in its CSS.
+    pub fn load(path: impl AsRef<Path>, regexes: &Regexes) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let template = std::fs::read_to_string(path).context(format!("Failed to read custom resume template {path:?}. Does it exist? Do we have permissions?"))?;
+        for warning in check_placeholders(&template, regexes).context(format!("{path:?} is not a valid resume template"))? {
+            tracing::warn!("{path:?}: {warning}");
+        }
+        Ok(Self::Custom { template: Arc::new(template) })
+    }
+
+    /// The HTML of this template, with its placeholders
+    pub fn html(&self) -> &str {
+        match self {
+            Self::Custom { template } => template,
+            Self::Default => DEFAULT_RESUME_HTML,
+            Self::Sidebar => SIDEBAR_RESUME_HTML
+        }
+    }
+
+    /// Moves each of the given sections to where the template marks their region with `<region-name>`,
+    /// such as `{ education = "sidebar" }`
+    ///
+    /// Sections moved to the same region stay in the order they were in.
+    pub fn with_regions(self, regions: &FxHashMap<String, String>) -> anyhow::Result<Self> {
+        if regions.is_empty() {
+            return Ok(self)
+        }
+        let mut template = self.html().to_string();
+        for name in regions.keys() {
+            if !SECTIONS.iter().any(|(open, _, _)| *open == format!("<{name}>")) {
+                return Err(anyhow::anyhow!("{name} is not a section of resume templates"))
+            }
+        }
+        for (open, close, _) in SECTIONS {
+            let Some(region) = regions.get(&open[1..open.len() - 1]) else { continue };
+            let mark = format!("<region-{region}>");
+            let (Some(start), Some(end)) = (template.find(open), template.rfind(close)) else { continue };
+            if start > end {
+                continue
+            }
+            if !template.contains(&mark) {
+                tracing::warn!("The template has no {mark}, so {open} is left where it is");
+                continue
+            }
+            let block: String = template.drain(start..end + close.len()).collect();
+            template = template.replacen(&mark, &(block + &mark), 1);
+        }
+        Ok(Self::Custom { template: Arc::new(template) })
+    }
+}
+
+
+/// Added as context to errors caused by the resume having text too small to be read, which
+/// will happen again until the template is changed
+#[derive(Debug)]
+pub struct FontTooSmall;
+
+
+impl std::fmt::Display for FontTooSmall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The resume template has text that is too small")
+    }
+}
+
+
+pub struct Regexes {
     name: Regex,
+    headline: Regex,
     phonenumber: Regex,
     email: Regex,
     website: Regex,
+    linkedin: Regex,
+    address: Regex,
+    contact: Regex,
     education: Regex,
     education_entries: Regex,
     school_name: Regex,
+    major: Regex,
     gpa: Regex,
-    max_gpa: Regex
+    max_gpa: Regex,
+    notes: Regex,
+    start_date: Regex,
+    end_date: Regex,
+    experience: Regex,
+    employer: Regex,
+    job_title: Regex,
+    location: Regex,
+    dates: Regex,
+    bullets: Regex,
+    label: Regex,
+    region: Regex,
+    /// Anything that looks like a placeholder with a hyphen in its name
+    hyphenated: Regex
 }
 
 
@@ -131,97 +344,622 @@ impl Default for Regexes {
     fn default() -> Self {
         Self {
             name: Regex::new("<name>").unwrap(),
+            headline: Regex::new("<headline>").unwrap(),
             phonenumber: Regex::new("<phonenumber>").unwrap(),
             email: Regex::new("<email>").unwrap(),
             website: Regex::new("<website>").unwrap(),
+            linkedin: Regex::new("<linkedin>").unwrap(),
+            address: Regex::new("<address>").unwrap(),
+            contact: Regex::new("<contact>").unwrap(),
             education: Regex::new("<education>(.|\n)*</education>").unwrap(),
             education_entries: Regex::new("<entries>(.|\n)*</entries>").unwrap(),
             school_name: Regex::new("<school-name>").unwrap(),
+            major: Regex::new("<major>").unwrap(),
             gpa: Regex::new("<gpa>").unwrap(),
             max_gpa: Regex::new("<max-gpa>").unwrap(),
+            notes: Regex::new("<notes>").unwrap(),
+            start_date: Regex::new("<start-date>").unwrap(),
+            end_date: Regex::new("<end-date>").unwrap(),
+            experience: Regex::new("<experience>(.|\n)*</experience>").unwrap(),
+            employer: Regex::new("<employer>").unwrap(),
+            job_title: Regex::new("<job-title>").unwrap(),
+            location: Regex::new("<location>").unwrap(),
+            dates: Regex::new("<dates>").unwrap(),
+            bullets: Regex::new("<bullets>").unwrap(),
+            label: Regex::new("<label-([a-z-]+)>").unwrap(),
+            region: Regex::new("<region-[a-z-]+>").unwrap(),
+            hyphenated: Regex::new("<([a-z]+(-[a-z]+)+)>").unwrap(),
         }
     }
 }
 
 
-pub(super) async fn use_page_data(page_data: PageData, tab: Arc<Tab>, resume_data: Arc<ResumeData>, resume_template: ResumeTemplate, regexes: Arc<Regexes>) -> anyhow::Result<()> {
-    let resume_bytes = tokio_rayon::spawn(move || {
-        let mut page_scale = 1.0;
-        let mut too_many_lines = false;
-        let (resume_body, min_font_size) = if let ResumeTemplate::Custom { template, min_font_size } = &resume_template {
-            (template.as_str(), *min_font_size)
-        } else {
-            (DEFAULT_RESUME_HTML, MIN_DEFAULT_RESUME_FONT_SIZE)
-        };
+/// Escapes the given text so that it can be put inside HTML, including inside quoted attributes
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
 
-        loop {
-            macro_rules! sub {
-                ($body: expr, $reg: ident, $($arg:tt)*) => {
-                    regexes.$reg.replace_all(&$body, $($arg)*)
-                };
-            }
+
+/// The `tel:` URL of the given phone number, such as `tel:+15551234567`
+///
+/// There is none if the phone number has anything other than digits and punctuation in it, such as when
+/// some of its digits were masked.
+fn tel_url(phone_number: &str) -> Option<String> {
+    if !phone_number.chars().all(|c| c.is_ascii_digit() || PHONE_NUMBER_PUNCTUATION.contains(&c)) {
+        return None
+    }
+    let number: String = phone_number.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+    (!number.is_empty()).then(|| format!("tel:{number}"))
+}
+
+
+/// A piece of contact info, as a link unless `plain_text` is true or there is nothing to link to
+///
+/// Links show `label` instead of the URL, while plain text shows `text`.
+fn contact_item(class: &str, text: &str, url: Option<&str>, label: Option<&str>, plain_text: bool) -> String {
+    match url {
+        Some(url) if !plain_text => format!("<a class=\"{class}\" href=\"{}\">{}</a>", escape_html(url), escape_html(label.unwrap_or(text))),
+        _ => format!("<div class=\"{class}\">{}</div>", escape_html(text))
+    }
+}
+
+
+/// Replaces every placeholder in the given template with what `selection` chose from the resume data,
+/// and labels with their translation
+fn substitute(resume_body: &str, resume_data: &ResumeData, selection: &Selection, translation: &Translation, regexes: &Regexes) -> String {
+    macro_rules! sub {
+        ($body: expr, $reg: ident, $($arg:tt)*) => {
+            regexes.$reg.replace_all(&$body, $($arg)*)
+        };
+    }
     
-            let resume_body = sub!(resume_body, name, |_: &Captures| format!("<div class=\"name\">{}</div>", resume_data.name));
-            let resume_body = sub!(resume_body, phonenumber, |_: &Captures| format!("<div class=\"phonenumber\">{}</div>", resume_data.phone_number));
-            let resume_body = sub!(resume_body, email, |_: &Captures| format!("<a class=\"email\" href=mailto:{}>Email</a>", resume_data.email));
-            let resume_body = match resume_data.website.as_ref() {
-                Some(website) => sub!(resume_body, website, |_: &Captures| format!("<a class=\"website\" href={website}>Website</a>")),
-                None => resume_body
-            };
-            let resume_body = sub!(resume_body, education, |c: &Captures| {
+    let resume_body = sub!(resume_body, region, "");
+    let resume_body = sub!(resume_body, label, |c: &Captures| translation.label(&c[1]).to_string());
+    let resume_body = sub!(resume_body, name, |_: &Captures| format!("<div class=\"name\">{}</div>", resume_data.name));
+    let resume_body = match &selection.headline {
+        Some(headline) => sub!(resume_body, headline, |_: &Captures| format!("<div class=\"headline\">{headline}</div>")),
+        None => sub!(resume_body, headline, "")
+    };
+
+    let plain_text = resume_data.plain_text_contact;
+    // Phone numbers are already readable, so they are not replaced by a label
+    let phone_number = contact_item("phonenumber", &resume_data.phone_number, tel_url(&resume_data.phone_number).as_deref(), None, plain_text);
+    let email = contact_item("email", &resume_data.email, Some(&format!("mailto:{}", resume_data.email)), Some(translation.label("email")), plain_text);
+    let website = resume_data.website.as_ref().map(|website| contact_item("website", website, Some(website), Some(translation.label("website")), plain_text));
+    let linkedin = contact_item("linkedin", &resume_data.linkedin, Some(&resume_data.linkedin), Some(translation.label("linkedin")), plain_text);
+    let address = resume_data.address.as_ref().map(|address| contact_item("address", address, None, None, plain_text));
+    // Only the contact info that is present, so that missing fields do not leave separators behind
+    let contact = [Some(&phone_number), Some(&email), website.as_ref(), Some(&linkedin), address.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(CONTACT_SEPARATOR);
+
+    let resume_body = sub!(resume_body, contact, |_: &Captures| format!("<div class=\"contact\">{contact}</div>"));
+    let resume_body = sub!(resume_body, phonenumber, |_: &Captures| phone_number.clone());
+    let resume_body = sub!(resume_body, email, |_: &Captures| email.clone());
+    let resume_body = match &website {
+        Some(website) => sub!(resume_body, website, |_: &Captures| website.clone()),
+        None => resume_body
+    };
+    let resume_body = sub!(resume_body, linkedin, |_: &Captures| linkedin.clone());
+    let resume_body = sub!(resume_body, address, |_: &Captures| address.clone().unwrap_or_default());
+    let resume_body = sub!(resume_body, education, |c: &Captures| {
+        let matched = c.get(0).unwrap().as_str();
+        // Remove <education> tags
+        let education_block = matched.split_at(matched.len() - 12).0.split_at(11).1;
+        regexes
+            .education_entries
+            .replace_all(education_block, |c: &Captures| {
                 let matched = c.get(0).unwrap().as_str();
-                // Remove <education> tags
-                let education_block = matched.split_at(matched.len() - 12).0.split_at(11).1;
-                regexes
-                    .education_entries
-                    .replace_all(education_block, |c: &Captures| {
-                        let matched = c.get(0).unwrap().as_str();
-                        // Remove <entries> tags
-                        let entry = matched.split_at(matched.len() - 10).0.split_at(9).1;
-
-                        resume_data.education
-                            .iter()
-                            .map(|education| {
-                                let entry = sub!(entry, school_name, |_: &Captures| format!("<div class=\"school-name\">{}</div>", education.school_name));
-                                let entry = sub!(entry, gpa, |_: &Captures| format!("<div class=\"gpa\">{}</div>", education.gpa));
-                                let entry = match education.max_gpa {
-                                    Some(x) => sub!(entry, max_gpa, |_: &Captures| format!("<div class=\"max-gpa\">/{x}</div>")),
-                                    None => entry
-                                };
-                                entry.into_owned()
-                            })
-                            .collect::<String>()
-                    }).into_owned()
-            });
+                // Remove <entries> tags
+                let entry = matched.split_at(matched.len() - 10).0.split_at(9).1;
 
-            tab
-                .navigate_to(&format!("data:text/html,{resume_body}"))?
-                .wait_until_navigated()?;
-            let mut height = tab.find_element("html").unwrap().get_box_model().unwrap().height;
-    
-            if height > A4_PAGE_HEIGHT_PX {
-                let new_page_scale = A4_PAGE_HEIGHT_PX / height;
-                height *= new_page_scale / page_scale;
-                page_scale = new_page_scale;
+                selection
+                    .education(resume_data)
+                    .map(|(education, notes)| {
+                        let entry = sub!(entry, school_name, |_: &Captures| format!("<div class=\"school-name\">{}</div>", education.school_name));
+                        let entry = sub!(entry, major, |_: &Captures| format!("<div class=\"major\">{}</div>", education.major));
+                        let entry = sub!(entry, gpa, |_: &Captures| format!("<div class=\"gpa\">{}</div>", education.gpa));
+                        let entry = match education.max_gpa {
+                            Some(x) => sub!(entry, max_gpa, |_: &Captures| format!("<div class=\"max-gpa\">/{x}</div>")),
+                            None => entry
+                        };
+                        let start = translation.format_date(education.start_year, education.start_month);
+                        let end = translation.format_date(education.end_year, education.end_month);
+                        let entry = sub!(entry, start_date, |_: &Captures| format!("<div class=\"start-date\">{start}</div>"));
+                        let entry = sub!(entry, end_date, |_: &Captures| format!("<div class=\"end-date\">{end}</div>"));
+                        // Left out entirely when no notes are relevant, so there is no empty list
+                        let notes = if notes.is_empty() {
+                            String::new()
+                        } else {
+                            let notes: String = notes.iter().map(|&i| format!("<li>{}</li>", education.notes[i])).collect();
+                            format!("<ul class=\"notes\">{notes}</ul>")
+                        };
+                        let entry = sub!(entry, notes, |_: &Captures| notes.clone());
+                        entry.into_owned()
+                    })
+                    .collect::<String>()
+            }).into_owned()
+    });
+    let resume_body = sub!(resume_body, experience, |c: &Captures| {
+        if selection.experience_entries.is_empty() {
+            return String::new()
+        }
+        let matched = c.get(0).unwrap().as_str();
+        // Remove <experience> tags
+        let experience_block = matched.split_at(matched.len() - 13).0.split_at(12).1;
+        regexes
+            .education_entries
+            .replace_all(experience_block, |c: &Captures| {
+                let matched = c.get(0).unwrap().as_str();
+                // Remove <entries> tags
+                let entry = matched.split_at(matched.len() - 10).0.split_at(9).1;
+
+                selection
+                    .experience(resume_data)
+                    .map(|(experience, bullets)| {
+                        let start = translation.format_date(experience.start_year, experience.start_month);
+                        let end = match experience.end_year {
+                            Some(end_year) => translation.format_date(end_year, experience.end_month.unwrap_or(12)),
+                            None => translation.label("present").to_string()
+                        };
+                        let bullets: String = bullets.iter().map(|&i| format!("<li>{}</li>", experience.bullets[i])).collect();
+
+                        let entry = sub!(entry, employer, |_: &Captures| format!("<div class=\"employer\">{}</div>", experience.employer));
+                        let entry = sub!(entry, job_title, |_: &Captures| format!("<div class=\"job-title\">{}</div>", experience.title));
+                        let entry = sub!(entry, location, |_: &Captures| format!("<div class=\"location\">{}</div>", experience.location.as_deref().unwrap_or_default()));
+                        let entry = sub!(entry, dates, |_: &Captures| format!("<div class=\"dates\">{start} – {end}</div>"));
+                        let entry = sub!(entry, start_date, |_: &Captures| format!("<div class=\"start-date\">{start}</div>"));
+                        let entry = sub!(entry, end_date, |_: &Captures| format!("<div class=\"end-date\">{end}</div>"));
+                        let entry = sub!(entry, bullets, |_: &Captures| format!("<ul class=\"bullets\">{bullets}</ul>"));
+                        entry.into_owned()
+                    })
+                    .collect::<String>()
+            }).into_owned()
+    });
+    resume_body.into_owned()
+}
+
+
+/// The most times the page scale is refined before settling on the best scale found so far
+const MAX_FIT_ITERATIONS: usize = 10;
+/// How many times fitting the resume to the page is tried before printing it at full size instead
+const FIT_ATTEMPTS: usize = 3;
+/// How long each attempt at fitting the resume to the page can take, in case Chrome is slow to respond
+const FIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+
+/// How many CSS pixels make up an inch in the document, as it is printed
+const PX_PER_INCH_JS: &str = "(() => {
+    const probe = document.createElement('div');
+    probe.style.cssText = 'position: absolute; visibility: hidden; height: 1in';
+    document.body.appendChild(probe);
+    const height = probe.getBoundingClientRect().height;
+    probe.remove();
+    return height;
+})()";
+
+
+/// The smallest computed font size of any visible text in the document, in pixels
+const MIN_FONT_SIZE_JS: &str = "(() => {
+    let min = Infinity;
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+    while (walker.nextNode()) {
+        const node = walker.currentNode;
+        if (!node.textContent.trim() || !node.parentElement) continue;
+        const style = getComputedStyle(node.parentElement);
+        if (style.display === 'none' || style.visibility === 'hidden') continue;
+        min = Math.min(min, parseFloat(style.fontSize));
+    }
+    return min === Infinity ? 16 : min;
+})()";
+
+
+fn evaluate_f64(tab: &Tab, expression: &str) -> anyhow::Result<f64> {
+    tab.evaluate(expression, false)?
+        .value
+        .and_then(|x| x.as_f64())
+        .context(format!("Failed to evaluate `{expression}` in the resume"))
+}
+
+
+/// The smallest print scale that keeps text of the given font size readable, at most 1
+fn min_scale(min_font_size: f64, area: PrintableArea) -> f64 {
+    (SMALLEST_FONT_PERCENTAGE * area.height / min_font_size).min(1.0)
+}
+
+
+/// The height of the document in the tab when it is printed at the given scale
+///
+/// Printing at a smaller scale gives the document more room horizontally, which changes how text wraps,
+/// so it is laid out at the width it would be printed at.
+fn printed_height(tab: &Tab, scale: f64, area: PrintableArea) -> anyhow::Result<f64> {
+    let height = evaluate_f64(tab, &format!("document.documentElement.style.width = '{}px'; document.documentElement.getBoundingClientRect().height", area.width / scale))?;
+    Ok(height * scale)
+}
+
+
+/// Whether the document in the tab fits on one page at the smallest scale that keeps its text readable
+fn fits_readably(tab: &Tab, min_font_size: f64, area: PrintableArea) -> anyhow::Result<bool> {
+    let height = printed_height(tab, min_scale(min_font_size, area), area)?;
+    tab.evaluate("document.documentElement.style.width = ''", false)?;
+    Ok(height <= area.height)
+}
+
+
+/// Finds the largest print scale (at most 1) at which the document in the tab fits on one page, using a binary search
+///
+/// The scale does not go below what keeps the smallest font readable. If the document does not fit
+/// even at that scale, a warning is printed and that scale is used anyway.
+fn fit_to_page(tab: &Tab, min_font_size: f64, area: PrintableArea) -> anyhow::Result<f64> {
+    let start = Instant::now();
+    let min_scale = min_scale(min_font_size, area);
+
+    // The height has to be measured again at every scale, as text wraps differently
+    let fits = |scale: f64| -> anyhow::Result<bool> {
+        if start.elapsed() > FIT_TIMEOUT {
+            return Err(anyhow::anyhow!("Chrome took longer than {FIT_TIMEOUT:?} to measure the resume"))
+        }
+        Ok(printed_height(tab, scale, area)? <= area.height)
+    };
+
+    let scale = if fits(1.0)? {
+        1.0
+    } else if !fits(min_scale)? {
+        tracing::warn!("The resume has too many lines to fit on one page while still being readable");
+        min_scale
+    } else {
+        let (mut low, mut high) = (min_scale, 1.0);
+        for _ in 0..MAX_FIT_ITERATIONS {
+            let middle = (low + high) / 2.0;
+            if fits(middle)? {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    };
+
+    tab.evaluate("document.documentElement.style.width = ''", false)?;
+    Ok(scale)
+}
+
+
+/// Replaces the document in the given tab with the given HTML
+///
+/// Unlike navigating to a `data:` URL, this has no length limit and the HTML does not need to be escaped.
+fn set_document_content(tab: &Tab, html: &str) -> anyhow::Result<()> {
+    tab.navigate_to("about:blank")?.wait_until_navigated()?;
+    let frame_id = tab.call_method(Page::GetFrameTree(None))?.frame_tree.frame.id;
+    tab.call_method(Page::SetDocumentContent { frame_id, html: html.to_string() })?;
+    Ok(())
+}
+
+
+/// Stored next to each resume, containing the `input_hash` it was rendered from
+const INPUT_HASH_FILE_NAME: &str = ".input-hash";
+/// Locked while a resume is being rendered into its folder
+const LOCK_FILE_NAME: &str = ".lock";
+/// Written next to each resume, describing the job that it was tailored to
+const JOB_METADATA_FILE_NAME: &str = "job.toml";
+/// How many of the job's keywords are listed in `JOB_METADATA_FILE_NAME`
+const JOB_METADATA_KEYWORDS: usize = 10;
+/// Stored next to each resume, with one ATS warning per line, so they can be reported even when rendering is skipped
+const ATS_WARNINGS_FILE_NAME: &str = ".ats-warnings";
+
+
+/// Finds the first `resume_v<n>.pdf` in the given folder that does not exist yet
+async fn next_version_path(folder_path: &Path) -> PathBuf {
+    for version in 1.. {
+        let path = folder_path.join(format!("resume_v{version}.pdf"));
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return path
+        }
+    }
+    unreachable!()
+}
+
+
+/// Hashes everything that affects how a resume is rendered
+fn input_hash(page_data: &PageData, resume_data: &ResumeData, selection: &Selection, translation: &Translation, resume_template: &ResumeTemplate, paper: Paper, backend: RenderBackend) -> u64 {
+    let mut hasher = FxHasher::default();
+    // Rendering may change between versions
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    toml::to_string(resume_data).unwrap_or_default().hash(&mut hasher);
+    toml::to_string(translation).unwrap_or_default().hash(&mut hasher);
+    resume_template.html().hash(&mut hasher);
+    paper.hash(&mut hasher);
+    backend.hash(&mut hasher);
+
+    page_data.url.hash(&mut hasher);
+    page_data.job_title.hash(&mut hasher);
+    page_data.company.hash(&mut hasher);
+    // Sets do not have a stable iteration order
+    let mut keywords: Vec<_> = page_data.keywords.iter().map(|k| (&k.key, k.data.score.to_bits())).collect();
+    keywords.sort();
+    keywords.hash(&mut hasher);
+    selection.education_entries.hash(&mut hasher);
+    selection.education_notes.hash(&mut hasher);
+    selection.headline.hash(&mut hasher);
+    selection.experience_entries.hash(&mut hasher);
+    selection.experience_bullets.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+
+/// The contents of `JOB_METADATA_FILE_NAME`
+#[derive(Serialize)]
+struct JobMetadata<'a> {
+    url: &'a str,
+    company: &'a str,
+    job_title: &'a str,
+    location: Option<&'a str>,
+    salary: Option<&'a str>,
+    scraped_at: toml::value::Datetime,
+    /// From 0 to 1
+    match_score: f32,
+    /// The job's highest scoring keywords, best first
+    top_keywords: Vec<&'a str>,
+    /// What was put on the resume, so that it can be compared with `diff` later
+    education: Vec<EducationMetadata<'a>>,
+    experience: Vec<ExperienceMetadata<'a>>
+}
+
+
+#[derive(Serialize)]
+struct EducationMetadata<'a> {
+    school_name: &'a str,
+    /// The notes that were chosen, most relevant first
+    notes: Vec<&'a str>
+}
+
+
+#[derive(Serialize)]
+struct ExperienceMetadata<'a> {
+    employer: &'a str,
+    title: &'a str,
+    /// The bullets that were chosen, most relevant first
+    bullets: Vec<&'a str>
+}
+
+
+/// Converts seconds since the unix epoch into a UTC datetime
+pub fn unix_to_datetime(secs: u64) -> toml::value::Datetime {
+    // Howard Hinnant's `civil_from_days`
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    let secs_of_day = secs % 86400;
+    toml::value::Datetime {
+        date: Some(toml::value::Date { year: year as u16, month: month as u8, day: day as u8 }),
+        time: Some(toml::value::Time {
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day / 60 % 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            nanosecond: 0
+        }),
+        offset: Some(toml::value::Offset::Z)
+    }
+}
+
+
+/// Writes `JOB_METADATA_FILE_NAME` into the given folder
+async fn write_job_metadata(folder_path: &Path, page_data: &PageData, resume_data: &ResumeData, selection: &Selection) -> anyhow::Result<()> {
+    let mut keywords: Vec<_> = page_data.keywords.iter().collect();
+    keywords.sort_by(|a, b| b.data.score.total_cmp(&a.data.score).then_with(|| a.key.cmp(&b.key)));
+    let metadata = JobMetadata {
+        url: page_data.url.as_str(),
+        company: &page_data.company,
+        job_title: &page_data.job_title,
+        location: page_data.location.as_deref(),
+        salary: page_data.salary.as_deref(),
+        scraped_at: unix_to_datetime(page_data.scraped_at),
+        match_score: selection.match_score,
+        top_keywords: keywords.into_iter().take(JOB_METADATA_KEYWORDS).map(|k| k.key.as_str()).collect(),
+        education: selection
+            .education(resume_data)
+            .map(|(education, notes)| EducationMetadata {
+                school_name: &education.school_name,
+                notes: notes.iter().map(|&i| education.notes[i].as_str()).collect()
+            })
+            .collect(),
+        experience: selection
+            .experience(resume_data)
+            .map(|(experience, bullets)| ExperienceMetadata {
+                employer: &experience.employer,
+                title: &experience.title,
+                bullets: bullets.iter().map(|&i| experience.bullets[i].as_str()).collect()
+            })
+            .collect()
+    };
+    let path = folder_path.join(JOB_METADATA_FILE_NAME);
+    tokio::fs::write(&path, toml::to_string_pretty(&metadata)?).await.context(format!("Failed to write {path:?}. Do we have permissions?"))
+}
+
+
+/// The name of the folder a resume for the given job is written into, if not given one
+pub fn resume_folder_name(page_data: &PageData) -> String {
+    format!("{} {}", page_data.company, page_data.job_title)
+}
+
+
+/// How a single resume is rendered by `generate_resume`, besides the job and the resume data
+pub struct RenderRequest<'a> {
+    /// What is chosen from the resume data to be put on the resume
+    pub selection: Selection,
+    /// The language that the labels of the template are in
+    pub translation: &'static Translation,
+    pub resume_template: ResumeTemplate,
+    pub paper: Paper,
+    pub paths: &'a ResumePaths,
+    /// Whether the previous resume is kept in its folder as `resume_v<n>.pdf` instead of being replaced
+    pub keep_old_versions: bool
+}
+
+
+/// A resume written by `generate_resume`
+pub struct GeneratedResume {
+    pub path: PathBuf,
+    /// How long rendering and writing the PDF took
+    pub timings: Timings,
+    /// Anything in the resume that ATS may have trouble reading
+    pub ats_warnings: Vec<String>,
+    /// Whether rendering was skipped, as the resume was already rendered from the exact same inputs
+    pub up_to_date: bool,
+    /// What was put on the resume, after leaving out any notes that did not fit. Not known if it was up to date
+    pub selection: Option<Selection>
+}
+
+
+/// Renders a resume tailored to the given job, as given by `request`
+///
+/// Only resumes rendered in Chrome are fit onto one page, and have their selectable text checked.
+///
+/// Rendering is skipped if the resume was already rendered from the exact same inputs.
+pub async fn generate_resume(page_data: PageData, renderer: Renderer, resume_data: Arc<ResumeData>, regexes: Arc<Regexes>, request: RenderRequest<'_>) -> anyhow::Result<GeneratedResume> {
+    let RenderRequest { selection, translation, resume_template, paper, paths, keep_old_versions } = request;
+    let folder_path = paths.folder.clone();
+    let resume_path = paths.resume.clone();
+    let hash_path = folder_path.join(INPUT_HASH_FILE_NAME);
+    let ats_warnings_path = folder_path.join(ATS_WARNINGS_FILE_NAME);
+    let input_hash = input_hash(&page_data, &resume_data, &selection, translation, &resume_template, paper, renderer.backend()).to_string();
+    DirBuilder::new().recursive(true).create(&folder_path).await.context(format!("Failed to create {folder_path:?}. Do we have permissions?"))?;
+
+    // Held until the resume is written, so that other instances of resume-builder do not write into the same folder
+    let lock_path = folder_path.join(LOCK_FILE_NAME);
+    let _lock = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let file = std::fs::File::create(&lock_path).context(format!("Failed to create {lock_path:?}. Do we have permissions?"))?;
+        file.lock_exclusive().context(format!("Failed to lock {lock_path:?}"))?;
+        Ok(file)
+    }).await??;
+
+    if tokio::fs::try_exists(&resume_path).await.unwrap_or(false) && tokio::fs::read_to_string(&hash_path).await.is_ok_and(|x| x == input_hash) {
+        tracing::info!("{resume_path:?} is already up to date");
+        // Written if missing, as older versions did not write them. Notes may have been left out to fit the page,
+        // so existing ones are kept as they are
+        if !tokio::fs::try_exists(folder_path.join(JOB_METADATA_FILE_NAME)).await.unwrap_or(false) {
+            write_job_metadata(&folder_path, &page_data, &resume_data, &selection).await?;
+        }
+        if !tokio::fs::try_exists(folder_path.join(EXPLANATION_FILE_NAME)).await.unwrap_or(false) {
+            write_explanation(&folder_path, &page_data, &resume_data, &selection).await?;
+        }
+        let ats_warnings = tokio::fs::read_to_string(&ats_warnings_path).await.unwrap_or_default();
+        return Ok(GeneratedResume {
+            path: resume_path,
+            timings: Timings::default(),
+            ats_warnings: ats_warnings.lines().map(ToString::to_string).collect(),
+            up_to_date: true,
+            selection: None
+        })
+    }
+
+    let mut timings = Timings::default();
+    let span = tracing::Span::current();
+    let render_data = resume_data.clone();
+    let (resume_bytes, ats_warnings, selection, rendering, printing) = tokio_rayon::spawn(move || -> anyhow::Result<_> {
+        let _span = span.enter();
+        let start = Instant::now();
+        let resume_body = resume_template.html();
+
+        let mut ats_warnings = ats::lint_template(resume_body);
+        let mut selection = selection;
+        let tab = match renderer {
+            Renderer::Chrome(tab) => tab,
+            Renderer::Program(backend, program) => {
+                let html = substitute(resume_body, &render_data, &selection, translation, &regexes);
+                let rendering = start.elapsed();
+                let start = Instant::now();
+                let resume_bytes = print_with_program(backend, program.as_deref(), &html, paper)?;
+                return Ok((resume_bytes, ats_warnings, selection, rendering, start.elapsed()))
             }
-            if min_font_size / height < SMALLEST_FONT_PERCENTAGE {
-                page_scale *= SMALLEST_FONT_PERCENTAGE / min_font_size * height;
-                too_many_lines = true;
-                continue;
+        };
+        // Notes are left out, least relevant first, before the resume is shrunk until it is unreadable
+        let (min_font_size, area) = loop {
+            let html = substitute(resume_body, &render_data, &selection, translation, &regexes);
+            set_document_content(&tab, &html)?;
+            // Measured as it will be printed, so that media queries for print are applied
+            tab.call_method(Emulation::SetEmulatedMedia { media: Some("print".into()), features: None })?;
+            let min_font_size = evaluate_f64(&tab, MIN_FONT_SIZE_JS)?;
+            let area = paper.printable_area(evaluate_f64(&tab, PX_PER_INCH_JS)?);
+            if min_font_size / area.height < SMALLEST_FONT_PERCENTAGE {
+                return Err(anyhow::anyhow!("The smallest font size ({min_font_size}px) in the resume is too small to be read").context(FontTooSmall))
             }
-            if page_scale < 1.0 {
-                page_scale = 1.0f64.min(A4_PAGE_HEIGHT_PX / height * page_scale);
+            if fits_readably(&tab, min_font_size, area)? || !(selection.leave_out_least_relevant_note() || selection.leave_out_least_relevant_education()) {
+                break (min_font_size, area)
             }
-    
-            break tab.print_to_pdf(Some(PrintToPdfOptions {
-                scale: Some(page_scale),
-                ..Default::default()
-            }))
-        }
+            tracing::debug!("Left out a note or education entry so that the resume fits on one page");
+        };
+        let page_scale = (1..=FIT_ATTEMPTS)
+            .find_map(|attempt| match fit_to_page(&tab, min_font_size, area) {
+                Ok(scale) => Some(scale),
+                Err(e) => {
+                    tracing::warn!("Failed to fit the resume to the page (attempt {attempt} of {FIT_ATTEMPTS}): {e:?}");
+                    None
+                }
+            })
+            .unwrap_or_else(|| {
+                tracing::warn!("Printing the resume at full size instead, so it may not fit on one page");
+                1.0
+            });
+        ats_warnings.extend(ats::lint_output(&tab, &render_data)?);
+        let rendering = start.elapsed();
+
+        let start = Instant::now();
+        let resume_bytes = tab.print_to_pdf(Some(paper.print_options(page_scale)))?;
+        Ok((resume_bytes, ats_warnings, selection, rendering, start.elapsed()))
     }).await?;
+    timings.rendering = rendering;
+    write_job_metadata(&folder_path, &page_data, &resume_data, &selection).await?;
+    write_explanation(&folder_path, &page_data, &resume_data, &selection).await?;
 
-    let folder_path = PathBuf::from(OUTPUT_PATH).join(format!("{} {}", page_data.company, page_data.job_title));
-    DirBuilder::new().recursive(true).create(&folder_path).await.context("Failed to create a directory in resumes. Do we have permissions?")?;
-    tokio::fs::write(folder_path.join("resume.pdf"), resume_bytes).await?;
-    Ok(())
+    let start = Instant::now();
+    // The previous resume may have already been sent to the company
+    if keep_old_versions && tokio::fs::try_exists(&resume_path).await.unwrap_or(false) {
+        let version_path = next_version_path(&folder_path).await;
+        tokio::fs::rename(&resume_path, &version_path).await.context(format!("Failed to move {resume_path:?} to {version_path:?}. Do we have permissions?"))?;
+        tracing::info!("Kept the previous resume as {version_path:?}");
+    }
+    tokio::fs::write(&resume_path, resume_bytes).await?;
+    tokio::fs::write(&ats_warnings_path, ats_warnings.join("\n")).await?;
+    tokio::fs::write(&hash_path, input_hash).await?;
+    timings.pdf_write = printing + start.elapsed();
+    Ok(GeneratedResume { path: resume_path, timings, ats_warnings, up_to_date: false, selection: Some(selection) })
+}
+
+
+/// What would be put on the resume for the given job, without rendering anything
+///
+/// Returned instead of printed, so that it can be printed without breaking up the progress bars.
+pub fn dry_run_preview(page_data: &PageData, resume_data: &ResumeData, limits: SelectionLimits, strategy: Strategy) -> String {
+    let selection = Selection::new(resume_data, page_data, limits, strategy);
+    let mut out = format!("{} {} ({})\n", page_data.company, page_data.job_title, page_data.url);
+    out += &format!("    Match score: {:.1}%\n", selection.match_score * 100.0);
+    if let Some(headline) = &selection.headline {
+        out += &format!("    Headline: {headline}\n");
+    }
+
+    for (education, notes) in selection.education(resume_data) {
+        out += &format!("    {}\n", education.school_name);
+        for &i in notes {
+            out += &format!("        - {}\n", education.notes[i]);
+        }
+    }
+    for (experience, bullets) in selection.experience(resume_data) {
+        out += &format!("    {} at {}\n", experience.title, experience.employer);
+        for &i in bullets {
+            out += &format!("        - {}\n", experience.bullets[i]);
+        }
+    }
+    out
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+//! The size of the paper that resumes are printed on, and how much of it can be printed on
+use std::hash::{Hash, Hasher};
+
+use headless_chrome::types::PrintToPdfOptions;
+use serde::Deserialize;
+
+/// The margins that Chrome prints with by default, in inches
+const DEFAULT_MARGIN_INCHES: f64 = 0.4;
+
+
+/// A standard paper size
+#[derive(Deserialize, Clone, Copy, Default, Debug, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperSize {
+    /// 8.5 by 11 inches, which Chrome prints on by default
+    #[default]
+    Letter,
+    /// 210 by 297 millimetres
+    A4,
+    /// 8.5 by 14 inches
+    Legal
+}
+
+
+/// The paper that resumes are printed on
+#[derive(Deserialize, Clone, Copy, Default)]
+pub struct Paper {
+    #[serde(default)]
+    pub size: PaperSize,
+    /// The margin on every side of the page. Defaults to 0.4 inches
+    pub margin_inches: Option<f64>
+}
+
+
+/// The part of a page inside its margins, in CSS pixels as measured in the tab
+#[derive(Clone, Copy)]
+pub struct PrintableArea {
+    pub width: f64,
+    pub height: f64
+}
+
+
+impl PaperSize {
+    /// The width and height of this paper, in inches
+    fn inches(self) -> (f64, f64) {
+        match self {
+            Self::Letter => (8.5, 11.0),
+            Self::A4 => (210.0 / 25.4, 297.0 / 25.4),
+            Self::Legal => (8.5, 14.0)
+        }
+    }
+}
+
+
+impl Paper {
+    fn margin(self) -> f64 {
+        self.margin_inches.unwrap_or(DEFAULT_MARGIN_INCHES)
+    }
+
+    /// The area that can be printed on, given how many CSS pixels make up an inch in the tab
+    pub fn printable_area(self, px_per_inch: f64) -> PrintableArea {
+        let (width, height) = self.size.inches();
+        PrintableArea {
+            width: (width - 2.0 * self.margin()) * px_per_inch,
+            height: (height - 2.0 * self.margin()) * px_per_inch
+        }
+    }
+
+    /// A CSS `@page` rule for this paper, for printing without Chrome
+    pub fn page_css(self) -> String {
+        let (width, height) = self.size.inches();
+        format!("@page {{ size: {width}in {height}in; margin: {}in; }}", self.margin())
+    }
+
+    /// The width, height and margin of this paper in inches
+    pub fn dimensions(self) -> (f64, f64, f64) {
+        let (width, height) = self.size.inches();
+        (width, height, self.margin())
+    }
+
+    /// Options for printing onto this paper at the given scale
+    pub fn print_options(self, scale: f64) -> PrintToPdfOptions {
+        let (width, height) = self.size.inches();
+        let margin = Some(self.margin());
+        PrintToPdfOptions {
+            scale: Some(scale),
+            paper_width: Some(width),
+            paper_height: Some(height),
+            margin_top: margin,
+            margin_bottom: margin,
+            margin_left: margin,
+            margin_right: margin,
+            ..Default::default()
+        }
+    }
+}
+
+
+impl Hash for Paper {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        self.margin().to_bits().hash(state);
+    }
+}
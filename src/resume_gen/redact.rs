@@ -0,0 +1,60 @@
+//! Resumes without private contact info, for posting on public job boards
+use serde::Deserialize;
+
+use super::ResumeData;
+
+/// The folder that the redacted version of a resume is written into, inside the folder of the full version
+pub const REDACTED_FOLDER_NAME: &str = "redacted";
+/// Put instead of each digit of the phone number that is masked
+const MASK: char = '•';
+/// How many digits at the end of the phone number are left unmasked
+const UNMASKED_DIGITS: usize = 4;
+
+
+fn yes() -> bool {
+    true
+}
+
+
+/// What is left out of the redacted version of resumes
+#[derive(Deserialize)]
+pub struct Redaction {
+    /// Mask all but the last few digits of the phone number. Defaults to true
+    #[serde(default = "yes")]
+    mask_phone_number: bool,
+    /// Leave out the address. Defaults to true
+    #[serde(default = "yes")]
+    omit_address: bool,
+    /// Put this email instead of yours, such as a forwarding alias
+    email_alias: Option<String>,
+    /// Only generate the redacted version of resumes, instead of both versions
+    #[serde(default)]
+    pub only_redacted: bool
+}
+
+
+impl Redaction {
+    pub fn redact(&self, resume_data: &ResumeData) -> ResumeData {
+        let mut resume_data = resume_data.clone();
+        if self.mask_phone_number {
+            let digits = resume_data.phone_number.chars().filter(char::is_ascii_digit).count();
+            let mut seen = 0;
+            resume_data.phone_number = resume_data.phone_number
+                .chars()
+                .map(|c| if c.is_ascii_digit() {
+                    seen += 1;
+                    if seen + UNMASKED_DIGITS > digits { c } else { MASK }
+                } else {
+                    c
+                })
+                .collect();
+        }
+        if self.omit_address {
+            resume_data.address = None;
+        }
+        if let Some(email_alias) = &self.email_alias {
+            resume_data.email = email_alias.clone();
+        }
+        resume_data
+    }
+}
@@ -0,0 +1,51 @@
+//! Changing what is put on a resume in the terminal before it is rendered
+use dialoguer::{theme::ColorfulTheme, MultiSelect};
+
+use crate::page_scrapers::PageData;
+
+use super::{selection::Selection, ResumeData};
+
+
+/// Lets the user toggle which of the given entries are chosen
+///
+/// Chosen entries are listed first, in the order they will be put on the resume, followed by the entries that
+/// were left out, in config order.
+fn review_entries(theme: &ColorfulTheme, prompt: String, entries: &[String], chosen: &mut Vec<usize>) -> anyhow::Result<()> {
+    if entries.is_empty() {
+        return Ok(())
+    }
+    let order: Vec<usize> = chosen
+        .iter()
+        .copied()
+        .chain((0..entries.len()).filter(|i| !chosen.contains(i)))
+        .collect();
+    let items: Vec<_> = order.iter().map(|&i| entries[i].as_str()).collect();
+    let defaults: Vec<_> = order.iter().map(|i| chosen.contains(i)).collect();
+
+    let toggled = MultiSelect::with_theme(theme)
+        .with_prompt(format!("{prompt} (space to toggle, enter to confirm)"))
+        .items(&items)
+        .defaults(&defaults)
+        .interact()?;
+    *chosen = toggled.into_iter().map(|i| order[i]).collect();
+    Ok(())
+}
+
+
+/// Shows what was chosen to be put on the resume for the given job, and lets the user toggle entries on or off
+pub fn review_selection(resume_data: &ResumeData, page_data: &PageData, mut selection: Selection) -> anyhow::Result<Selection> {
+    let theme = ColorfulTheme::default();
+    println!("{} {} ({})", page_data.company, page_data.job_title, page_data.url);
+    println!("Match score: {:.1}%", selection.match_score * 100.0);
+
+    for &i in &selection.education_entries {
+        let (education, chosen) = (&resume_data.education[i], &mut selection.education_notes[i]);
+        review_entries(&theme, format!("Notes for {}", education.school_name), &education.notes, chosen)?;
+    }
+    for &i in &selection.experience_entries {
+        let (experience, chosen) = (&resume_data.experience[i], &mut selection.experience_bullets[i]);
+        review_entries(&theme, format!("Bullets for {} at {}", experience.title, experience.employer), &experience.bullets, chosen)?;
+    }
+
+    Ok(selection)
+}
@@ -0,0 +1,360 @@
+//! Choosing which of the resume data is relevant enough to a job to be put on its resume
+use fxhash::FxHashSet;
+use serde::Deserialize;
+
+use crate::page_scrapers::PageData;
+
+use super::{Education, Experience, ResumeData};
+
+/// The most bullets put under each experience entry, if not limited by `SelectionLimits`
+pub const MAX_EXPERIENCE_BULLETS: usize = 3;
+
+
+/// How much of the resume data can be put on a single resume, so that it fits on one page
+/// without shrinking the text
+#[derive(Default, Clone, Copy)]
+pub struct SelectionLimits {
+    /// The most experience entries on a resume. The most relevant ones are chosen, in config order.
+    /// There is no limit by default
+    pub max_experience_entries: Option<usize>,
+    /// The most bullets under each experience entry. Defaults to `MAX_EXPERIENCE_BULLETS`
+    pub max_bullets_per_entry: Option<usize>,
+    /// The order education entries are put on the resume in
+    pub education_order: EducationOrder,
+    /// Leave out the least relevant education entries if the resume would not fit on one page otherwise,
+    /// once there are no more notes to leave out. At least one is always kept
+    pub drop_education_entries: bool
+}
+
+
+/// The order that education entries are put on a resume in
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum EducationOrder {
+    /// The most recently finished first
+    #[default]
+    ReverseChronological,
+    /// The order they are written in config.toml
+    Config,
+    /// The most relevant to the job first
+    Relevance
+}
+
+
+/// How the notes, bullets and experience entries that are put on a resume are chosen
+pub trait SelectionStrategy: Send + Sync {
+    /// Orders the given notes, bullets or experience entries from most to least worth putting on the resume
+    /// for the given job, along with how relevant each one is
+    ///
+    /// Notes with no relevance are left out. Bullets and experience entries are still chosen if there is room.
+    fn rank(&self, texts: &[String], page_data: &PageData) -> Vec<(usize, f32)>;
+}
+
+
+/// Chooses whatever has the most keyword weight, even if it repeats keywords that were already chosen
+pub struct Greedy;
+
+/// Chooses whatever has the most keywords that were not already chosen, so that the resume covers as many
+/// of the job's keywords as it can
+pub struct Coverage;
+
+/// Ignores the job's keywords, and chooses everything in the order it is written in config.toml
+pub struct Manual;
+
+
+impl SelectionStrategy for Greedy {
+    fn rank(&self, texts: &[String], page_data: &PageData) -> Vec<(usize, f32)> {
+        let mut ranked: Vec<_> = texts
+            .iter()
+            .map(|text| relevance(text, page_data))
+            .enumerate()
+            .collect();
+        // Stable sort, so ties keep the order given in the config
+        ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        ranked
+    }
+}
+
+
+impl SelectionStrategy for Coverage {
+    fn rank(&self, texts: &[String], page_data: &PageData) -> Vec<(usize, f32)> {
+        let found: Vec<FxHashSet<&str>> = texts
+            .iter()
+            .map(|text| {
+                let text = text.to_lowercase();
+                page_data.keywords
+                    .iter()
+                    .filter(|k| text.contains(&k.key.to_lowercase()))
+                    .map(|k| k.key.as_str())
+                    .collect()
+            })
+            .collect();
+        let mut covered = FxHashSet::default();
+        let mut remaining: Vec<usize> = (0..texts.len()).collect();
+        let mut ranked = vec![];
+        while !remaining.is_empty() {
+            // The earliest one is taken if several cover the same number of new keywords
+            let (position, new_keywords) = remaining
+                .iter()
+                .map(|&i| found[i].difference(&covered).count())
+                .enumerate()
+                .reduce(|best, x| if x.1 > best.1 { x } else { best })
+                .unwrap();
+            let i = remaining.remove(position);
+            covered.extend(found[i].iter().copied());
+            ranked.push((i, new_keywords as f32));
+        }
+        ranked
+    }
+}
+
+
+impl SelectionStrategy for Manual {
+    fn rank(&self, texts: &[String], _page_data: &PageData) -> Vec<(usize, f32)> {
+        (0..texts.len()).map(|i| (i, 1.0)).collect()
+    }
+}
+
+
+/// The built-in `SelectionStrategy`s that can be chosen in config.toml
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    #[default]
+    Greedy,
+    Coverage,
+    Manual
+}
+
+
+impl Strategy {
+    pub fn get(self) -> &'static dyn SelectionStrategy {
+        match self {
+            Self::Greedy => &Greedy,
+            Self::Coverage => &Coverage,
+            Self::Manual => &Manual
+        }
+    }
+}
+
+
+/// The parts of `ResumeData` that were chosen to be put on a resume for a specific job
+#[derive(Clone)]
+pub struct Selection {
+    /// What is put under your name, from `ResumeData::headline`
+    pub headline: Option<String>,
+    /// The indices of the education entries that were chosen, in the order they are put on the resume
+    pub education_entries: Vec<usize>,
+    /// For each education entry (in config order), the indices of the notes that were chosen,
+    /// with the most relevant note first
+    pub education_notes: Vec<Vec<usize>>,
+    /// The indices of the experience entries that were chosen, in config order
+    pub experience_entries: Vec<usize>,
+    /// For each experience entry (in config order), the indices of the bullets that were chosen,
+    /// with the most relevant bullet first. Entries that were not chosen keep their bullets too
+    pub experience_bullets: Vec<Vec<usize>>,
+    /// The fraction of the job's keyword weight that is covered by the resume data, from 0 to 1
+    pub match_score: f32,
+    /// How relevant each education entry is to the job, in config order
+    education_relevance: Vec<f32>,
+    /// Whether the least relevant education entries can be left out to fit the resume on one page
+    drop_education_entries: bool
+}
+
+
+/// The keywords of the job that appear in the given text, along with their scores, highest first
+pub fn matched_keywords<'a>(text: &str, page_data: &'a PageData) -> Vec<(&'a str, f32)> {
+    let text = text.to_lowercase();
+    let mut matched: Vec<_> = page_data.keywords
+        .iter()
+        .filter(|k| text.contains(&k.key.to_lowercase()))
+        .map(|k| (k.key.as_str(), k.data.score))
+        .collect();
+    matched.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    matched
+}
+
+
+/// Sums the scores of the keywords of the job that appear in the given text
+fn relevance(text: &str, page_data: &PageData) -> f32 {
+    matched_keywords(text, page_data).into_iter().map(|(_, score)| score).sum()
+}
+
+
+/// Everything in the resume data that keywords are looked for in, lowercased
+pub fn resume_text(resume_data: &ResumeData) -> String {
+    let education = resume_data.education
+        .iter()
+        .flat_map(|education| [&education.school_name, &education.major].into_iter().chain(&education.notes));
+    let experience = resume_data.experience
+        .iter()
+        .flat_map(|experience| [&experience.employer, &experience.title].into_iter().chain(&experience.bullets));
+    education
+        .chain(experience)
+        .map(|x| x.to_lowercase() + "\n")
+        .collect()
+}
+
+
+/// Everything that was chosen to be put on the resume that keywords are looked for in, lowercased
+pub fn chosen_text(resume_data: &ResumeData, selection: &Selection) -> String {
+    let education = selection
+        .education(resume_data)
+        .flat_map(|(education, notes)| [&education.school_name, &education.major].into_iter().chain(notes.iter().map(|&i| &education.notes[i])));
+    let experience = selection
+        .experience(resume_data)
+        .flat_map(|(experience, bullets)| [&experience.employer, &experience.title].into_iter().chain(bullets.iter().map(|&i| &experience.bullets[i])));
+    education
+        .chain(experience)
+        .map(|x| x.to_lowercase() + "\n")
+        .collect()
+}
+
+
+impl Selection {
+    pub fn new(resume_data: &ResumeData, page_data: &PageData, limits: SelectionLimits, strategy: Strategy) -> Self {
+        let strategy = strategy.get();
+        let education_notes = resume_data.education
+            .iter()
+            .map(|education| {
+                strategy.rank(&education.notes, page_data)
+                    .into_iter()
+                    .filter(|(_, relevance)| *relevance > 0.0)
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect();
+        let education_texts: Vec<_> = resume_data.education
+            .iter()
+            .map(|education| format!("{}\n{}\n{}", education.school_name, education.major, education.notes.join("\n")))
+            .collect();
+        let mut education_relevance = vec![0.0; resume_data.education.len()];
+        for (i, relevance) in strategy.rank(&education_texts, page_data) {
+            education_relevance[i] = relevance;
+        }
+        let mut education_entries: Vec<_> = (0..resume_data.education.len()).collect();
+        match limits.education_order {
+            EducationOrder::ReverseChronological => {
+                let end = |i: usize| (resume_data.education[i].end_year, resume_data.education[i].end_month);
+                education_entries.sort_by_key(|&i| std::cmp::Reverse(end(i)));
+            }
+            EducationOrder::Config => {}
+            EducationOrder::Relevance => education_entries.sort_by(|&a, &b| education_relevance[b].total_cmp(&education_relevance[a]))
+        }
+        // Unlike notes, every experience entry should have bullets, even if none of them are relevant
+        let experience_bullets = resume_data.experience
+            .iter()
+            .map(|experience| {
+                strategy.rank(&experience.bullets, page_data)
+                    .into_iter()
+                    .take(limits.max_bullets_per_entry.unwrap_or(MAX_EXPERIENCE_BULLETS))
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect();
+        let entry_texts: Vec<_> = resume_data.experience
+            .iter()
+            .map(|experience| format!("{}\n{}", experience.title, experience.bullets.join("\n")))
+            .collect();
+        let mut experience_entries: Vec<_> = strategy.rank(&entry_texts, page_data)
+            .into_iter()
+            .take(limits.max_experience_entries.unwrap_or(usize::MAX))
+            .map(|(i, _)| i)
+            .collect();
+        experience_entries.sort();
+
+        let resume_text = resume_text(resume_data);
+        let total: f32 = page_data.keywords.iter().map(|k| k.data.score).sum();
+        let match_score = if total > 0.0 {
+            relevance(&resume_text, page_data) / total
+        } else {
+            0.0
+        };
+
+        let headline = resume_data.headline.as_ref().and_then(|x| x.for_job(&page_data.job_title));
+
+        Self {
+            headline,
+            education_entries,
+            education_notes,
+            experience_entries,
+            experience_bullets,
+            match_score,
+            education_relevance,
+            drop_education_entries: limits.drop_education_entries
+        }
+    }
+
+    /// The education entries that were chosen, in the order they are put on the resume, along with their chosen notes
+    pub(super) fn education<'a>(&'a self, resume_data: &'a ResumeData) -> impl Iterator<Item = (&'a Education, &'a Vec<usize>)> {
+        self.education_entries.iter().map(|&i| (&resume_data.education[i], &self.education_notes[i]))
+    }
+
+    /// The experience entries that were chosen, in config order, along with their chosen bullets
+    pub(super) fn experience<'a>(&'a self, resume_data: &'a ResumeData) -> impl Iterator<Item = (&'a Experience, &'a Vec<usize>)> {
+        self.experience_entries.iter().map(|&i| (&resume_data.experience[i], &self.experience_bullets[i]))
+    }
+
+    /// Also chooses every note that was left out, after the chosen ones and in config order
+    pub fn with_all_notes(mut self, resume_data: &ResumeData) -> Self {
+        for (education, chosen) in resume_data.education.iter().zip(&mut self.education_notes) {
+            for i in 0..education.notes.len() {
+                if !chosen.contains(&i) {
+                    chosen.push(i);
+                }
+            }
+        }
+        self
+    }
+
+    /// Leaves out the least relevant note of the chosen education entry with the most notes, the last such entry if
+    /// there are several, so that the resume fits on one page
+    ///
+    /// Returns false if there were no notes left to leave out.
+    pub fn leave_out_least_relevant_note(&mut self) -> bool {
+        let education_notes = &mut self.education_notes;
+        match self.education_entries.iter().max_by_key(|&&i| education_notes[i].len()) {
+            Some(&i) if !education_notes[i].is_empty() => {
+                education_notes[i].pop();
+                true
+            }
+            _ => false
+        }
+    }
+
+    /// Leaves out the least relevant education entry, the last one on the resume if there are several,
+    /// so that the resume fits on one page
+    ///
+    /// Returns false if only one is left, or if entries cannot be left out.
+    pub fn leave_out_least_relevant_education(&mut self) -> bool {
+        if !self.drop_education_entries || self.education_entries.len() <= 1 {
+            return false
+        }
+        let relevance = &self.education_relevance;
+        let (position, _) = self.education_entries
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by(|a, b| relevance[*a.1].total_cmp(&relevance[*b.1]))
+            .unwrap();
+        self.education_entries.remove(position);
+        true
+    }
+
+    /// Keeps only the `max` most relevant notes of each education entry
+    pub fn truncate_notes(&mut self, max: usize) {
+        for chosen in &mut self.education_notes {
+            chosen.truncate(max);
+        }
+    }
+
+    /// How many notes were chosen here but not in `other`, and how many were chosen in `other` but not here
+    pub fn note_changes(&self, other: &Self) -> (usize, usize) {
+        let only_in = |a: &Self, b: &Self| a.education_notes
+            .iter()
+            .zip(&b.education_notes)
+            .map(|(a, b)| a.iter().filter(|i| !b.contains(i)).count())
+            .sum();
+        (only_in(self, other), only_in(other, self))
+    }
+}
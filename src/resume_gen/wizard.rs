@@ -0,0 +1,164 @@
+//! Filling in the resume data of config.toml by answering prompts in the terminal
+use std::path::Path;
+
+use anyhow::Context;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
+use validator::Validate;
+
+use super::{Education, Experience, ResumeData};
+
+
+/// Prompts for a line of text that must not be empty
+fn required(theme: &ColorfulTheme, prompt: &str) -> anyhow::Result<String> {
+    Input::with_theme(theme)
+        .with_prompt(prompt)
+        .validate_with(|x: &String| if x.trim().is_empty() { Err("This field is required") } else { Ok(()) })
+        .interact_text()
+        .map_err(Into::into)
+}
+
+
+/// Prompts for a line of text that may be left empty
+fn optional(theme: &ColorfulTheme, prompt: &str, validator: fn(&str) -> bool, error: &'static str) -> anyhow::Result<Option<String>> {
+    let input: String = Input::with_theme(theme)
+        .with_prompt(format!("{prompt} (leave empty to skip)"))
+        .allow_empty(true)
+        .validate_with(|x: &String| if x.is_empty() || validator(x) { Ok(()) } else { Err(error) })
+        .interact_text()?;
+
+    Ok(if input.is_empty() { None } else { Some(input) })
+}
+
+
+/// Prompts for a list of lines, ending when an empty line is entered
+fn list(theme: &ColorfulTheme, prompt: &str) -> anyhow::Result<Vec<String>> {
+    let mut entries = vec![];
+    loop {
+        let entry: String = Input::with_theme(theme)
+            .with_prompt(format!("{prompt} #{} (leave empty to finish)", entries.len() + 1))
+            .allow_empty(true)
+            .interact_text()?;
+        if entry.is_empty() {
+            break Ok(entries)
+        }
+        entries.push(entry);
+    }
+}
+
+
+/// Prompts for a number until one in the given range is entered
+macro_rules! ranged {
+    ($theme: expr, $prompt: expr, $min: expr, $max: expr) => {
+        Input::with_theme($theme)
+            .with_prompt($prompt)
+            .validate_with(|x: &_| if ($min..=$max).contains(x) { Ok(()) } else { Err(concat!("Must be between ", $min, " and ", $max)) })
+            .interact_text()?
+    };
+}
+
+
+fn education(theme: &ColorfulTheme) -> anyhow::Result<Education> {
+    let school_name = required(theme, "School name")?;
+    let major = required(theme, "Major")?;
+    let gpa: f64 = Input::with_theme(theme)
+        .with_prompt("Cumulative GPA")
+        .validate_with(|x: &f64| if *x >= 0.0 { Ok(()) } else { Err("GPA cannot be negative") })
+        .interact_text()?;
+    let max_gpa = optional(theme, "Maximum possible GPA", |x| x.parse::<f64>().is_ok_and(|x| x >= 0.0), "Not a valid GPA")?
+        .map(|x| x.parse().unwrap());
+
+    let start_year: u16 = ranged!(theme, "Start year", 1970, 2070);
+    let start_month: u8 = ranged!(theme, "Start month (1-12)", 1, 12);
+    let end_year: u16 = ranged!(theme, "End year (or expected graduation year)", 1970, 2070);
+    let end_month: u8 = ranged!(theme, "End month (1-12)", 1, 12);
+    let notes = list(theme, "Note")?;
+
+    Ok(Education { gpa, max_gpa, start_year, start_month, end_year, end_month, school_name, major, notes })
+}
+
+
+fn experience(theme: &ColorfulTheme) -> anyhow::Result<Experience> {
+    let employer = required(theme, "Employer")?;
+    let title = required(theme, "Job title")?;
+    let location = optional(theme, "Location", |_| true, "")?;
+    let start_year: u16 = ranged!(theme, "Start year", 1970, 2070);
+    let start_month: u8 = ranged!(theme, "Start month (1-12)", 1, 12);
+    let (end_year, end_month) = if Confirm::with_theme(theme).with_prompt("Do you still work here?").default(false).interact()? {
+        (None, None)
+    } else {
+        let end_year: u16 = ranged!(theme, "End year", 1970, 2070);
+        let end_month: u8 = ranged!(theme, "End month (1-12)", 1, 12);
+        (Some(end_year), Some(end_month))
+    };
+    let bullets = list(theme, "Bullet")?;
+
+    Ok(Experience { employer, title, location, start_year, start_month, end_year, end_month, bullets })
+}
+
+
+/// Walks through every field of `ResumeData` in the terminal, then writes the
+/// result into the `resume_data` section of the config at the given path.
+///
+/// The rest of the config is preserved if it already exists.
+pub fn run_wizard(config_path: &Path) -> anyhow::Result<()> {
+    let theme = ColorfulTheme::default();
+
+    let mut config: toml::Table = if config_path.try_exists().context("Failed to check if the config exists. Do we have permissions?")? {
+        let config = std::fs::read_to_string(config_path).context("Failed to read the config. Do we have permissions?")?;
+        toml::from_str(&config)?
+    } else {
+        Default::default()
+    };
+
+    if config.contains_key("resume_data") && !Confirm::with_theme(&theme)
+        .with_prompt(format!("{config_path:?} already has resume data. Overwrite it?"))
+        .default(false)
+        .interact()?
+    {
+        return Ok(())
+    }
+
+    let name = required(&theme, "Full name")?;
+    let phone_number = Input::with_theme(&theme)
+        .with_prompt("Phone number")
+        .validate_with(|x: &String| if validator::validate_phone(x.as_str()) { Ok(()) } else { Err("Not a valid phone number. Try including the country code, e.g. +1") })
+        .interact_text()?;
+    let email = Input::with_theme(&theme)
+        .with_prompt("Email")
+        .validate_with(|x: &String| if validator::validate_email(x.as_str()) { Ok(()) } else { Err("Not a valid email") })
+        .interact_text()?;
+    let website = optional(&theme, "Website", |x| validator::validate_url(x), "Not a valid URL")?;
+    let linkedin = Input::with_theme(&theme)
+        .with_prompt("LinkedIn URL")
+        .validate_with(|x: &String| if validator::validate_url(x.as_str()) { Ok(()) } else { Err("Not a valid URL") })
+        .interact_text()?;
+    let address = optional(&theme, "Address", |_| true, "")?;
+
+    let mut educations = vec![];
+    while Confirm::with_theme(&theme)
+        .with_prompt(if educations.is_empty() { "Add an education entry?" } else { "Add another education entry?" })
+        .default(educations.is_empty())
+        .interact()?
+    {
+        educations.push(education(&theme)?);
+    }
+
+    let mut experiences = vec![];
+    while Confirm::with_theme(&theme)
+        .with_prompt(if experiences.is_empty() { "Add a work experience entry?" } else { "Add another work experience entry?" })
+        .default(experiences.is_empty())
+        .interact()?
+    {
+        experiences.push(experience(&theme)?);
+    }
+
+    let resume_data = ResumeData { name, phone_number, email, website, linkedin, address, plain_text_contact: false, headline: None, education: educations, experience: experiences };
+    resume_data.validate()?;
+
+    config.insert("resume_data".into(), toml::Value::try_from(&resume_data)?);
+    config.entry("job_requirement_websites").or_insert_with(|| toml::Value::Array(vec![]));
+
+    std::fs::write(config_path, toml::to_string_pretty(&config)?).context("Failed to write the config. Do we have permissions?")?;
+    println!("Resume data written to {config_path:?}");
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use std::{sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex}, ops::Deref, io::{Read, Write}, net::TcpStream, path::PathBuf, ffi::OsStr};
+
+use anyhow::Context;
+use headless_chrome::{browser::default_executable, Browser, LaunchOptions, Tab};
+use serde::Deserialize;
+
+
+/// How Chrome is found or started
+#[derive(Deserialize, Default, Clone, PartialEq)]
+pub struct BrowserConfig {
+    /// The DevTools websocket URL of an already running Chrome, such as
+    /// `ws://127.0.0.1:9222/devtools/browser/<id>`
+    pub websocket_url: Option<String>,
+    /// The remote debugging port of an already running Chrome on this machine,
+    /// as given to Chrome with `--remote-debugging-port`
+    pub debugging_port: Option<u16>,
+    /// The Chrome or Chromium executable to start. Found in the usual places if not given
+    pub chrome_path: Option<PathBuf>,
+    /// Download a known good build of Chromium if `chrome_path` is not given, instead of looking for
+    /// an installed Chrome. It is only downloaded once
+    #[serde(default)]
+    pub download_chrome: bool,
+    /// Which headless mode Chrome is started in. Defaults to `"old"`
+    #[serde(default)]
+    pub headless: HeadlessMode,
+    /// Start Chrome without its sandbox, which cannot start inside most containers or as root
+    #[serde(default)]
+    pub no_sandbox: bool,
+    /// Have Chrome use temporary files instead of `/dev/shm`, which is too small in Docker by default
+    #[serde(default)]
+    pub disable_dev_shm_usage: bool,
+    /// The width and height of Chrome's window, such as `[1920, 1080]`
+    pub window_size: Option<(u32, u32)>,
+    /// Any other arguments to start Chrome with, such as `["--disable-gpu"]`
+    #[serde(default)]
+    pub args: Vec<String>
+}
+
+
+/// Whether Chrome is started without a window
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadlessMode {
+    /// Chrome's original headless mode
+    #[default]
+    Old,
+    /// Chrome's newer headless mode, which behaves more like a normal Chrome
+    New,
+    /// Show Chrome's window
+    Off
+}
+
+
+impl BrowserConfig {
+    /// Asks the Chrome listening on the given port for its DevTools websocket URL
+    fn discover_websocket_url(port: u16) -> anyhow::Result<String> {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).context(format!("Failed to connect to Chrome on port {port}. Is it running with --remote-debugging-port={port}?"))?;
+        write!(stream, "GET /json/version HTTP/1.0\r\nHost: 127.0.0.1:{port}\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let (_, body) = response.split_once("\r\n\r\n").context("Chrome sent an invalid response to /json/version")?;
+        let version: serde_json::Value = serde_json::from_str(body)?;
+        version["webSocketDebuggerUrl"]
+            .as_str()
+            .map(ToString::to_string)
+            .context("Chrome did not give a websocket URL in /json/version")
+    }
+
+    /// Checks that `chrome_path` exists, before anything needs Chrome
+    ///
+    /// Chrome is not looked for otherwise, as runs that only use cached websites do not need it.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match &self.chrome_path {
+            Some(path) if !path.is_file() => Err(anyhow::anyhow!("browser.chrome_path is {path:?}, which does not exist")),
+            _ => Ok(())
+        }
+    }
+
+    /// Starts a new Chrome
+    fn launch(&self) -> anyhow::Result<Browser> {
+        // Chromium is only downloaded if no path is given
+        let path = match &self.chrome_path {
+            Some(path) => Some(path.clone()),
+            None if self.download_chrome => None,
+            None => Some(default_executable().map_err(|e| anyhow::anyhow!(e)).context("Failed to find Chrome. Install Chrome, set browser.chrome_path to where it is, or set browser.download_chrome = true to download Chromium")?)
+        };
+        let mut args: Vec<&OsStr> = self.args.iter().map(OsStr::new).collect();
+        if self.headless == HeadlessMode::New {
+            args.push(OsStr::new("--headless=new"));
+        }
+        if self.disable_dev_shm_usage {
+            args.push(OsStr::new("--disable-dev-shm-usage"));
+        }
+        let options = LaunchOptions::default_builder()
+            .path(path)
+            .headless(self.headless == HeadlessMode::Old)
+            .sandbox(!self.no_sandbox)
+            .window_size(self.window_size)
+            .args(args)
+            .build()
+            .context("Invalid options for starting Chrome")?;
+        Browser::new(options).context("Failed to start Headless Chrome")
+    }
+
+    /// Connects to the configured Chrome, or starts a new one if none was configured
+    pub fn open(&self) -> anyhow::Result<Browser> {
+        let websocket_url = match (&self.websocket_url, self.debugging_port) {
+            (Some(url), _) => url.clone(),
+            (None, Some(port)) => Self::discover_websocket_url(port)?,
+            (None, None) => return self.launch()
+        };
+        Browser::connect(websocket_url.clone()).context(format!("Failed to connect to Chrome at {websocket_url}"))
+    }
+}
+
+
+/// Added as context to errors caused by Chrome not being able to be started or connected to
+#[derive(Debug)]
+pub struct BrowserUnavailable;
+
+
+impl std::fmt::Display for BrowserUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Chrome is unavailable")
+    }
+}
+
+
+/// A Chrome that is only started (or connected to) once it is first needed,
+/// and can be shared between several `TabPool`s
+///
+/// If Chrome crashes or is closed, a new one is started the next time it is needed.
+pub struct SharedBrowser {
+    config: BrowserConfig,
+    browser: Mutex<Option<Browser>>,
+    /// How many times Chrome has been started or connected to, so that tabs of one that crashed are not reused
+    generation: AtomicUsize
+}
+
+
+impl SharedBrowser {
+    pub fn new(config: BrowserConfig) -> Self {
+        Self { config, browser: Mutex::new(None), generation: AtomicUsize::new(0) }
+    }
+
+    /// The running Chrome, along with its generation
+    pub fn get(&self) -> anyhow::Result<(Browser, usize)> {
+        let mut browser = self.browser.lock().unwrap();
+        if browser.as_ref().is_some_and(|x| x.get_version().is_err()) {
+            tracing::warn!("Chrome stopped responding, so it is being started again");
+            *browser = None;
+        }
+        if browser.is_none() {
+            *browser = Some(self.config.open().context(BrowserUnavailable)?);
+            self.generation.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok((browser.clone().unwrap(), self.generation.load(Ordering::Relaxed)))
+    }
+}
+
+
+/// A bounded set of Chrome tabs that are reused across websites or resumes
+///
+/// Chrome is only started once the first tab is acquired.
+pub struct TabPool {
+    browser: Arc<SharedBrowser>,
+    /// Along with the generation of the Chrome they were opened in
+    idle: Mutex<Vec<(Arc<Tab>, usize)>>,
+    /// The maximum number of idle tabs to keep open
+    size: usize
+}
+
+
+/// A tab that is returned to its `TabPool` when dropped
+pub struct PooledTab {
+    tab: Arc<Tab>,
+    pool: Arc<TabPool>,
+    /// The generation of the Chrome that the tab was opened in
+    generation: usize,
+    /// Whether the tab is closed when dropped, instead of being returned to the pool
+    discarded: bool
+}
+
+
+impl TabPool {
+    pub fn new(size: usize, browser: Arc<SharedBrowser>) -> Self {
+        Self { browser, idle: Mutex::new(vec![]), size }
+    }
+
+    /// Takes an idle tab, or opens a new one if there are none
+    ///
+    /// Chrome is started again first if it crashed, in which case its idle tabs are thrown away.
+    pub fn acquire(self: &Arc<Self>) -> anyhow::Result<PooledTab> {
+        let (browser, generation) = self.browser.get()?;
+        let idle = {
+            let mut idle = self.idle.lock().unwrap();
+            idle.retain(|(_, x)| *x == generation);
+            idle.pop()
+        };
+        let tab = match idle {
+            Some((tab, _)) => tab,
+            None => browser.new_tab()?
+        };
+        Ok(PooledTab { tab, pool: self.clone(), generation, discarded: false })
+    }
+}
+
+
+impl PooledTab {
+    /// A handle to the tab that can be moved onto other threads
+    ///
+    /// The tab is only returned to the pool once this `PooledTab` is dropped, so it should be kept
+    /// alive for as long as the handle is in use.
+    pub fn tab(&self) -> Arc<Tab> {
+        self.tab.clone()
+    }
+
+    /// Closes the tab instead of returning it to the pool, such as when it stopped responding
+    pub fn discard(mut self) {
+        self.discarded = true;
+    }
+}
+
+
+impl Deref for PooledTab {
+    type Target = Tab;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tab
+    }
+}
+
+
+impl Drop for PooledTab {
+    fn drop(&mut self) {
+        let mut idle = self.pool.idle.lock().unwrap();
+        if !self.discarded && idle.len() < self.pool.size {
+            idle.push((self.tab.clone(), self.generation));
+        } else {
+            drop(idle);
+            let _ = self.tab.close(false);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::{fmt, ops::AddAssign, time::Duration};
+
+
+/// How long each phase of processing a website took, as reported by `--timings`
+#[derive(Default, Clone, Copy)]
+pub struct Timings {
+    /// Navigating to the website in Chrome, or fetching it over HTTP
+    pub navigation: Duration,
+    /// Running the scrapers, which includes keyword extraction
+    pub scraping: Duration,
+    /// Running the keyword extraction model
+    pub keyword_extraction: Duration,
+    /// Filling in the template and fitting it onto one page
+    pub rendering: Duration,
+    /// Printing the resume to a PDF and writing it to disk
+    pub pdf_write: Duration
+}
+
+
+impl AddAssign for Timings {
+    fn add_assign(&mut self, rhs: Self) {
+        self.navigation += rhs.navigation;
+        self.scraping += rhs.scraping;
+        self.keyword_extraction += rhs.keyword_extraction;
+        self.rendering += rhs.rendering;
+        self.pdf_write += rhs.pdf_write;
+    }
+}
+
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "navigation {:.2}s, scraping {:.2}s (keyword extraction {:.2}s), rendering {:.2}s, pdf write {:.2}s",
+            self.navigation.as_secs_f64(),
+            self.scraping.as_secs_f64(),
+            self.keyword_extraction.as_secs_f64(),
+            self.rendering.as_secs_f64(),
+            self.pdf_write.as_secs_f64()
+        )
+    }
+}
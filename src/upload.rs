@@ -0,0 +1,346 @@
+//! Uploading generated resumes to cloud storage, so they can be reached from other devices
+use std::{fmt::Write, path::{Component, Path}};
+
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::resume_gen::unix_to_datetime;
+
+
+/// Where resumes are uploaded to, picked with `backend`
+///
+/// Resumes are put where they are in the output directory, such as `<company> <job title>/resume.pdf`,
+/// under the given folder, path, or prefix. Existing resumes at the same place are replaced.
+#[derive(Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum UploadConfig {
+    Dropbox {
+        /// An access token of a Dropbox app with the `files.content.write` scope
+        access_token: String,
+        /// The folder to upload into, such as "/Resumes"
+        #[serde(default)]
+        path: String
+    },
+    GoogleDrive {
+        /// An OAuth access token with the `drive.file` scope
+        access_token: String,
+        /// The ID of the folder to upload into, as seen in its URL. Defaults to the root of My Drive
+        folder_id: Option<String>
+    },
+    S3 {
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+        /// Put before the key of every resume, such as "resumes/"
+        #[serde(default)]
+        prefix: String,
+        /// For S3 compatible services. Defaults to `https://s3.<region>.amazonaws.com`
+        endpoint: Option<String>
+    }
+}
+
+
+impl UploadConfig {
+    /// Uploads a resume to `name`, its path inside the output directory
+    pub async fn upload(&self, client: &reqwest::Client, name: &Path, resume: Vec<u8>) -> anyhow::Result<()> {
+        let mut folders: Vec<String> = name
+            .components()
+            .filter_map(|x| match x {
+                Component::Normal(x) => Some(x.to_string_lossy().into_owned()),
+                _ => None
+            })
+            .collect();
+        let file_name = folders.pop().context(format!("{name:?} has no file name"))?;
+        let name = folders.iter().chain([&file_name]).map(String::as_str).collect::<Vec<_>>().join("/");
+        match self {
+            Self::Dropbox { access_token, path } => {
+                let path = format!("{}/{name}", path.trim_end_matches('/'));
+                let arg = serde_json::json!({ "path": path, "mode": "overwrite", "mute": true });
+                client
+                    .post("https://content.dropboxapi.com/2/files/upload")
+                    .bearer_auth(access_token)
+                    .header("Dropbox-API-Arg", arg.to_string())
+                    .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+                    .body(resume)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .context(format!("Failed to upload {path} to Dropbox"))?;
+            }
+            Self::GoogleDrive { access_token, folder_id } => {
+                let mut parent = folder_id.clone().unwrap_or_else(|| "root".into());
+                for folder in &folders {
+                    parent = match drive_find(client, access_token, &parent, folder, true).await? {
+                        Some(id) => id,
+                        None => drive_create_folder(client, access_token, &parent, folder).await?
+                    };
+                }
+                drive_upload(client, access_token, &parent, &file_name, resume).await.context(format!("Failed to upload {name} to Google Drive"))?;
+            }
+            Self::S3 { bucket, region, access_key_id, secret_access_key, prefix, endpoint } => {
+                let key = format!("{prefix}{name}");
+                s3_put(client, S3Request { bucket, region, access_key_id, secret_access_key, endpoint: endpoint.as_deref(), key: &key }, resume)
+                    .await
+                    .context(format!("Failed to upload {key} to S3"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+const DRIVE_FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+
+#[derive(Deserialize)]
+struct DriveFile {
+    id: String
+}
+
+#[derive(Deserialize)]
+struct DriveFiles {
+    files: Vec<DriveFile>
+}
+
+
+/// Finds the ID of a folder or file with the given name in the given folder
+async fn drive_find(client: &reqwest::Client, access_token: &str, parent: &str, name: &str, folder: bool) -> anyhow::Result<Option<String>> {
+    let escape = |x: &str| x.replace('\\', "\\\\").replace('\'', "\\'");
+    let mut query = format!("name = '{}' and '{}' in parents and trashed = false", escape(name), escape(parent));
+    if folder {
+        query += &format!(" and mimeType = '{DRIVE_FOLDER_MIME_TYPE}'");
+    }
+    let files: DriveFiles = client
+        .get("https://www.googleapis.com/drive/v3/files")
+        .bearer_auth(access_token)
+        .query(&[("q", query.as_str()), ("fields", "files(id)")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(files.files.into_iter().next().map(|x| x.id))
+}
+
+
+async fn drive_create_folder(client: &reqwest::Client, access_token: &str, parent: &str, name: &str) -> anyhow::Result<String> {
+    let folder: DriveFile = client
+        .post("https://www.googleapis.com/drive/v3/files")
+        .bearer_auth(access_token)
+        .json(&serde_json::json!({ "name": name, "mimeType": DRIVE_FOLDER_MIME_TYPE, "parents": [parent] }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(folder.id)
+}
+
+
+/// Uploads a resume with the given file name into the given folder, replacing the contents of the one already there
+async fn drive_upload(client: &reqwest::Client, access_token: &str, parent: &str, file_name: &str, resume: Vec<u8>) -> anyhow::Result<()> {
+    if let Some(id) = drive_find(client, access_token, parent, file_name, false).await? {
+        client
+            .patch(format!("https://www.googleapis.com/upload/drive/v3/files/{id}?uploadType=media"))
+            .bearer_auth(access_token)
+            .header(reqwest::header::CONTENT_TYPE, "application/pdf")
+            .body(resume)
+            .send()
+            .await?
+            .error_for_status()?;
+        return Ok(())
+    }
+
+    const BOUNDARY: &str = "resume-builder-boundary";
+    let metadata = serde_json::json!({ "name": file_name, "parents": [parent] });
+    let mut body = format!("--{BOUNDARY}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{metadata}\r\n--{BOUNDARY}\r\nContent-Type: application/pdf\r\n\r\n").into_bytes();
+    body.extend(resume);
+    body.extend(format!("\r\n--{BOUNDARY}--").into_bytes());
+    client
+        .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=multipart")
+        .bearer_auth(access_token)
+        .header(reqwest::header::CONTENT_TYPE, format!("multipart/related; boundary={BOUNDARY}"))
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+
+struct S3Request<'a> {
+    bucket: &'a str,
+    region: &'a str,
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    endpoint: Option<&'a str>,
+    key: &'a str
+}
+
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC should accept keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+
+/// Percent encodes everything but the characters that AWS leaves unreserved
+fn aws_uri_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+
+/// The key that requests for the given date (such as "20130524"), region and service are signed with
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    [region, service, "aws4_request"]
+        .into_iter()
+        .fold(hmac_sha256(format!("AWS4{secret_access_key}").as_bytes(), date), |key, x| hmac_sha256(&key, x))
+}
+
+
+/// The `x-amz-date` of a request made the given number of seconds after the unix epoch, such as "20130524T000000Z"
+fn amz_date(secs: u64) -> String {
+    unix_to_datetime(secs).to_string().replace(['-', ':'], "")
+}
+
+
+/// The names of the given headers, as listed in `SignedHeaders`
+fn signed_headers(headers: &[(&str, &str)]) -> String {
+    headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";")
+}
+
+
+/// The AWS Signature Version 4 of a request to S3 without a query string
+///
+/// `headers` are every signed header, with lowercase names and sorted by name.
+fn s3_signature(secret_access_key: &str, region: &str, method: &str, path: &str, headers: &[(&str, &str)], payload_hash: &str, amz_date: &str) -> String {
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{name}:{value}\n")).collect();
+    let canonical_request = format!("{method}\n{path}\n\n{canonical_headers}\n{}\n{payload_hash}", signed_headers(headers));
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}", hex::encode(Sha256::digest(canonical_request.as_bytes())));
+    hex::encode(hmac_sha256(&signing_key(secret_access_key, date, region, "s3"), &string_to_sign))
+}
+
+
+/// Puts an object with a path style request, signed with AWS Signature Version 4
+async fn s3_put(client: &reqwest::Client, request: S3Request<'_>, body: Vec<u8>) -> anyhow::Result<()> {
+    let endpoint = request.endpoint.map(ToString::to_string).unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", request.region));
+    let endpoint: url::Url = endpoint.parse().context("The S3 endpoint is not a valid URL")?;
+    let host = match endpoint.port() {
+        Some(port) => format!("{}:{port}", endpoint.host_str().context("The S3 endpoint has no host")?),
+        None => endpoint.host_str().context("The S3 endpoint has no host")?.to_string()
+    };
+    let path = format!("/{}/{}", aws_uri_encode(request.bucket), request.key.split('/').map(aws_uri_encode).collect::<Vec<_>>().join("/"));
+
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let amz_date = amz_date(now);
+    let payload_hash = hex::encode(Sha256::digest(&body));
+
+    let headers = [("host", host.as_str()), ("x-amz-content-sha256", payload_hash.as_str()), ("x-amz-date", amz_date.as_str())];
+    let signature = s3_signature(request.secret_access_key, request.region, "PUT", &path, &headers, &payload_hash, &amz_date);
+    let scope = format!("{}/{}/s3/aws4_request", &amz_date[..8], request.region);
+    let signed_headers = signed_headers(&headers);
+
+    client
+        .put(format!("{}://{host}{path}", endpoint.scheme()))
+        .header("x-amz-date", &amz_date)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header(reqwest::header::AUTHORIZATION, format!("AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}", request.access_key_id))
+        .header(reqwest::header::CONTENT_TYPE, "application/pdf")
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    // The examples of https://docs.aws.amazon.com/AmazonS3/latest/API/sig-v4-header-based-auth.html
+    const SECRET_ACCESS_KEY: &str = "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY";
+    const EXAMPLE_HOST: &str = "examplebucket.s3.amazonaws.com";
+    const EXAMPLE_DATE: &str = "20130524T000000Z";
+
+
+    #[test]
+    fn signs_get_object_example() {
+        let payload_hash = hex::encode(Sha256::digest(b""));
+        let headers = [("host", EXAMPLE_HOST), ("range", "bytes=0-9"), ("x-amz-content-sha256", payload_hash.as_str()), ("x-amz-date", EXAMPLE_DATE)];
+        assert_eq!(
+            s3_signature(SECRET_ACCESS_KEY, "us-east-1", "GET", "/test.txt", &headers, &payload_hash, EXAMPLE_DATE),
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+    }
+
+
+    #[test]
+    fn signs_put_object_example() {
+        let payload_hash = hex::encode(Sha256::digest(b"Welcome to Amazon S3."));
+        assert_eq!(payload_hash, "44ce7dd67c959e0d3524ffac1771dfbba87d2b6b4b4e99e42034a8b803f8b072");
+        let path = format!("/{}", aws_uri_encode("test$file.text"));
+        assert_eq!(path, "/test%24file.text");
+        let headers = [
+            ("date", "Fri, 24 May 2013 00:00:00 GMT"),
+            ("host", EXAMPLE_HOST),
+            ("x-amz-content-sha256", payload_hash.as_str()),
+            ("x-amz-date", EXAMPLE_DATE),
+            ("x-amz-storage-class", "REDUCED_REDUNDANCY")
+        ];
+        assert_eq!(
+            s3_signature(SECRET_ACCESS_KEY, "us-east-1", "PUT", &path, &headers, &payload_hash, EXAMPLE_DATE),
+            "98ad721746da40c64f1a55b78f14c238d841ea1380cd77a1b5971af0ece108bd"
+        );
+    }
+
+
+    // The example of https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html
+    #[test]
+    fn derives_example_signing_key() {
+        assert_eq!(
+            hex::encode(signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam")),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+
+    #[test]
+    fn encodes_like_aws() {
+        assert_eq!(aws_uri_encode("Acme Inc/Engineer I+II~v1.pdf"), "Acme%20Inc%2FEngineer%20I%2BII~v1.pdf");
+        assert_eq!(aws_uri_encode("Café"), "Caf%C3%A9");
+    }
+
+
+    #[test]
+    fn formats_amz_dates() {
+        assert_eq!(amz_date(0), "19700101T000000Z");
+        assert_eq!(amz_date(1369353600), EXAMPLE_DATE);
+        assert_eq!(amz_date(1709164800 + 45296), "20240229T123456Z");
+    }
+
+
+    #[test]
+    fn converts_leap_days() {
+        // 2000 is a leap year as it is divisible by 400, but 2100 is not
+        assert_eq!(unix_to_datetime(951782400).to_string(), "2000-02-29T00:00:00Z");
+        assert_eq!(unix_to_datetime(951782400 + 86400).to_string(), "2000-03-01T00:00:00Z");
+        assert_eq!(unix_to_datetime(4107542400 - 1).to_string(), "2100-02-28T23:59:59Z");
+        assert_eq!(unix_to_datetime(4107542400).to_string(), "2100-03-01T00:00:00Z");
+    }
+}